@@ -1,9 +1,29 @@
 #![no_std]
 use soroban_sdk::{
-    contract, contractimpl, contracttype, token, Address, BytesN, Env, Map, String, Symbol, Vec,
+    contract, contractimpl, contracttype, token, Address, BytesN, Env, Map, String, Symbol,
+    TryFromVal, Val, Vec,
 };
+use soroban_sdk::xdr::ToXdr;
 use soroban_auth::{Identifier, Signature};
 
+// Storage schema version written by this code
+// Version 1 is the original layout, before contributions carried a reference
+// and withdrawals needed multiple approvals, reserved the pool balance and expired,
+// and before pools could be closed or carry savings goals
+const CURRENT_SCHEMA_VERSION: u32 = 2;
+
+// Maximum number of pools converted by a single migrate call
+const MAX_MIGRATION_BATCH: u32 = 10;
+
+// Seconds a withdrawal request stays open before it expires (7 days)
+const WITHDRAWAL_EXPIRY: u64 = 7 * 24 * 60 * 60;
+
+// Shortest interval between allowance payments (1 day)
+const MIN_ALLOWANCE_INTERVAL: u64 = 24 * 60 * 60;
+
+// Maximum number of allowance payments made by a single disburse_due call
+const MAX_DISBURSEMENTS: u32 = 20;
+
 // Member role in the family pool
 #[derive(Clone, Copy, PartialEq, Eq)]
 #[contracttype]
@@ -21,6 +41,16 @@ pub enum WithdrawalStatus {
     Approved,
     Rejected,
     Completed,
+    Cancelled,
+    Expired,
+}
+
+// Status of a pool
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[contracttype]
+pub enum PoolStatus {
+    Active,
+    Closed,
 }
 
 // Withdrawal period for limits
@@ -50,6 +80,26 @@ pub struct Contribution {
     pub amount: i128,
     pub token: Address,
     pub created_at: u64,
+    pub reference: Option<BytesN<32>>, // Remittance ID for deposits made by a remittance contract
+}
+
+// Contribution layout of schema version 1, kept to migrate old records
+#[derive(Clone)]
+#[contracttype]
+pub struct LegacyContribution {
+    pub id: BytesN<32>,
+    pub contributor: Address,
+    pub amount: i128,
+    pub token: Address,
+    pub created_at: u64,
+}
+
+// Number of admin approvals needed for withdrawals of at least `min_amount`
+#[derive(Clone)]
+#[contracttype]
+pub struct ApprovalTier {
+    pub min_amount: i128,
+    pub approvals: u32,
 }
 
 // Withdrawal data structure
@@ -64,6 +114,121 @@ pub struct Withdrawal {
     pub status: WithdrawalStatus,
     pub created_at: u64,
     pub completed_at: u64,
+    pub approvals: Vec<Address>,
+    pub rejections: Vec<Address>,
+    pub approved_at: u64,
+    pub expires_at: u64,
+}
+
+// Withdrawal layout of schema version 1, kept to migrate old records
+#[derive(Clone)]
+#[contracttype]
+pub struct LegacyWithdrawal {
+    pub id: BytesN<32>,
+    pub requester: Address,
+    pub recipient: Address,
+    pub amount: i128,
+    pub token: Address,
+    pub status: WithdrawalStatus,
+    pub created_at: u64,
+    pub completed_at: u64,
+}
+
+// How the beneficiary of each rotating savings cycle is chosen
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[contracttype]
+pub enum PayoutOrder {
+    Fixed,  // Members in the order they joined the pool
+    Random, // Shuffled with the ledger PRNG at the start of each round
+    Bid,    // The member giving up the largest part of the pot, which stays in the pool
+}
+
+// Pot paid out at the end of a rotating savings cycle
+#[derive(Clone)]
+#[contracttype]
+pub struct RoscaPayout {
+    pub cycle: u32,
+    pub beneficiary: Address,
+    pub amount: i128,
+    pub paid_at: u64,
+}
+
+// Rotating savings (chama / merry-go-round) state of a pool
+// Every participant contributes a fixed amount each cycle and one of them receives the pot,
+// until each participant has received it once in the round
+#[derive(Clone)]
+#[contracttype]
+pub struct Rosca {
+    pub contribution: i128,
+    pub cycle_length: u64,
+    pub payout_order: PayoutOrder,
+    pub order: Vec<Address>,
+    pub cycle: u32,
+    pub cycle_started_at: u64,
+    pub contributed: Vec<Address>, // Participants who contributed in the current cycle
+    pub paid: Vec<Address>,        // Participants who received the pot in the current round
+    pub bids: Map<Address, i128>,
+    pub defaults: Map<Address, u32>, // Number of missed cycles per participant
+    pub payouts: Vec<RoscaPayout>,
+}
+
+// Recurring payment from a pool to a recipient, paid by disburse_due
+#[derive(Clone)]
+#[contracttype]
+pub struct AllowanceSchedule {
+    pub id: u32,
+    pub recipient: Address,
+    pub amount: i128,
+    pub interval: u64,
+    pub next_due: u64,
+    pub end_date: u64,
+    pub created_by: Address,
+    pub active: bool,
+}
+
+// Contribution a member promised to make every period
+// Changed and cancelled pledges are kept with their end time, so their arrears stay owed
+#[derive(Clone)]
+#[contracttype]
+pub struct Pledge {
+    pub member: Address,
+    pub amount: i128,
+    pub period: WithdrawalPeriod,
+    pub start: u64,
+    pub end: u64, // 0 while the pledge is in force
+}
+
+// Pledge fulfilment of a member, from their contributions in each pledge period
+#[derive(Clone)]
+#[contracttype]
+pub struct Arrears {
+    pub member: Address,
+    pub pledged: i128,        // Amount pledged per period, 0 without a pledge in force
+    pub periods: u32,         // Pledge periods that have ended
+    pub missed_periods: u32,  // Ended periods not fully contributed
+    pub outstanding: i128,    // Unpaid pledges of ended periods
+    pub current_paid: i128,   // Contributed in the current period
+}
+
+// Savings goal of a pool
+#[derive(Clone)]
+#[contracttype]
+pub struct SavingsGoal {
+    pub target: i128,
+    pub deadline: u64,
+    pub lock_withdrawals: bool, // Lock withdrawals until the target is reached or the deadline passes
+    pub created_at: u64,
+}
+
+// Progress towards a pool's savings goal, counting contributions made since the goal was set
+#[derive(Clone)]
+#[contracttype]
+pub struct GoalProgress {
+    pub target: i128,
+    pub contributed: i128,
+    pub deadline: u64,
+    pub reached: bool,
+    pub withdrawals_locked: bool,
 }
 
 // Family Pool data structure
@@ -78,6 +243,26 @@ pub struct FamilyPool {
     pub withdrawal_limit: i128,
     pub withdrawal_period: WithdrawalPeriod,
     pub created_at: u64,
+    pub approval_tiers: Vec<ApprovalTier>,
+    pub reserved: i128, // Amount locked by open withdrawals
+    pub status: PoolStatus,
+    pub close_votes: Vec<Address>,
+    pub closed_at: u64,
+    pub goal: Option<SavingsGoal>,
+}
+
+// Family Pool layout of schema version 1, kept to migrate old records
+#[derive(Clone)]
+#[contracttype]
+pub struct LegacyFamilyPool {
+    pub id: BytesN<32>,
+    pub name: String,
+    pub creator: Address,
+    pub token: Address,
+    pub balance: i128,
+    pub withdrawal_limit: i128,
+    pub withdrawal_period: WithdrawalPeriod,
+    pub created_at: u64,
 }
 
 // Contract storage keys
@@ -90,6 +275,13 @@ pub enum DataKey {
     PoolWithdrawals(BytesN<32>),
     UserPools(Address),
     PoolCount,
+    Admin,
+    SchemaVersion,
+    Rosca(BytesN<32>),
+    Allowances(BytesN<32>),
+    Pledges(BytesN<32>),
+    LegacyPoolCount,
+    MigratedPoolCount,
 }
 
 #[contract]
@@ -97,6 +289,218 @@ pub struct FamilyPoolContract;
 
 #[contractimpl]
 impl FamilyPoolContract {
+    // Initialize the contract with the admin allowed to upgrade it
+    pub fn initialize(env: Env, admin: Address) {
+        // Verify admin
+        admin.require_auth();
+
+        // Check if contract is already initialized
+        if env.storage().instance().has(&DataKey::Admin) {
+            panic!("Contract already initialized");
+        }
+
+        env.storage().instance().set(&DataKey::Admin, &admin);
+
+        // Pools created by older code still have to be migrated
+        if !env.storage().instance().has(&DataKey::PoolCount) {
+            env.storage()
+                .instance()
+                .set(&DataKey::SchemaVersion, &CURRENT_SCHEMA_VERSION);
+        }
+    }
+
+    // Upgrade the contract code (admin only)
+    // Run migrate afterwards if the new code bumps the schema version
+    pub fn upgrade(env: Env, admin: Address, new_wasm_hash: BytesN<32>) {
+        // Verify admin
+        admin.require_auth();
+        require_admin(&env, &admin);
+
+        env.deployer().update_current_contract_wasm(new_wasm_hash);
+    }
+
+    // Convert the records of a batch of pools from schema version 1 to the current layout (admin only)
+    // Records already in the current layout are kept. Returns the number of converted records.
+    pub fn migrate(env: Env, admin: Address, pool_ids: Vec<BytesN<32>>) -> u32 {
+        // Verify admin
+        admin.require_auth();
+        require_admin(&env, &admin);
+
+        if schema_version(&env) >= CURRENT_SCHEMA_VERSION {
+            panic!("Storage already migrated");
+        }
+
+        if pool_ids.len() > MAX_MIGRATION_BATCH {
+            panic!("Migration batch too large");
+        }
+
+        let reference_key = Symbol::new(&env, "reference");
+        let expires_at_key = Symbol::new(&env, "expires_at");
+        let goal_key = Symbol::new(&env, "goal");
+        // Record how many old pools there are before converting any
+        legacy_pool_count(&env);
+
+        let mut migrated = 0u32;
+        let mut migrated_pools = 0u32;
+        for pool_id in pool_ids.iter() {
+            let raw_withdrawals = env
+                .storage()
+                .instance()
+                .get::<_, Vec<Map<Symbol, Val>>>(&DataKey::PoolWithdrawals(pool_id.clone()))
+                .expect("Withdrawals not found");
+
+            let mut withdrawals = Vec::<Withdrawal>::new(&env);
+            for raw in raw_withdrawals.iter() {
+                if raw.contains_key(expires_at_key.clone()) {
+                    withdrawals.push_back(
+                        Withdrawal::try_from_val(&env, &raw.to_val())
+                            .expect("Unknown withdrawal layout"),
+                    );
+                    continue;
+                }
+
+                let legacy = LegacyWithdrawal::try_from_val(&env, &raw.to_val())
+                    .expect("Unknown withdrawal layout");
+
+                // Version 1 paid out withdrawals as soon as they were approved
+                let (status, approved_at) = if legacy.status == WithdrawalStatus::Approved {
+                    (WithdrawalStatus::Completed, legacy.completed_at)
+                } else {
+                    (legacy.status, 0)
+                };
+                withdrawals.push_back(Withdrawal {
+                    id: legacy.id,
+                    requester: legacy.requester,
+                    recipient: legacy.recipient,
+                    amount: legacy.amount,
+                    token: legacy.token,
+                    status,
+                    created_at: legacy.created_at,
+                    completed_at: legacy.completed_at,
+                    approvals: Vec::new(&env),
+                    rejections: Vec::new(&env),
+                    approved_at,
+                    expires_at: legacy.created_at + WITHDRAWAL_EXPIRY,
+                });
+                migrated += 1;
+            }
+
+            env.storage()
+                .instance()
+                .set(&DataKey::PoolWithdrawals(pool_id.clone()), &withdrawals);
+
+            let raw_pool = env
+                .storage()
+                .instance()
+                .get::<_, Map<Symbol, Val>>(&DataKey::Pool(pool_id.clone()))
+                .expect("Pool not found");
+            if !raw_pool.contains_key(goal_key.clone()) {
+                // Open withdrawals lock their amount from the pool balance
+                let reserved = withdrawals
+                    .iter()
+                    .filter(|w| {
+                        w.status == WithdrawalStatus::Pending || w.status == WithdrawalStatus::Approved
+                    })
+                    .fold(0, |total, w| total + w.amount);
+
+                let legacy = LegacyFamilyPool::try_from_val(&env, &raw_pool.to_val())
+                    .expect("Unknown pool layout");
+                let pool = FamilyPool {
+                    id: legacy.id,
+                    name: legacy.name,
+                    creator: legacy.creator,
+                    token: legacy.token,
+                    balance: legacy.balance,
+                    withdrawal_limit: legacy.withdrawal_limit,
+                    withdrawal_period: legacy.withdrawal_period,
+                    created_at: legacy.created_at,
+                    approval_tiers: Vec::new(&env),
+                    reserved,
+                    status: PoolStatus::Active,
+                    close_votes: Vec::new(&env),
+                    closed_at: 0,
+                    goal: None,
+                };
+                env.storage()
+                    .instance()
+                    .set(&DataKey::Pool(pool_id.clone()), &pool);
+                migrated += 1;
+                migrated_pools += 1;
+            }
+
+            let raw_contributions = env
+                .storage()
+                .instance()
+                .get::<_, Vec<Map<Symbol, Val>>>(&DataKey::PoolContributions(pool_id.clone()))
+                .expect("Contributions not found");
+
+            let mut contributions = Vec::<Contribution>::new(&env);
+            for raw in raw_contributions.iter() {
+                if raw.contains_key(reference_key.clone()) {
+                    contributions.push_back(
+                        Contribution::try_from_val(&env, &raw.to_val())
+                            .expect("Unknown contribution layout"),
+                    );
+                    continue;
+                }
+
+                let legacy = LegacyContribution::try_from_val(&env, &raw.to_val())
+                    .expect("Unknown contribution layout");
+                contributions.push_back(Contribution {
+                    id: legacy.id,
+                    contributor: legacy.contributor,
+                    amount: legacy.amount,
+                    token: legacy.token,
+                    created_at: legacy.created_at,
+                    reference: None,
+                });
+                migrated += 1;
+            }
+
+            env.storage()
+                .instance()
+                .set(&DataKey::PoolContributions(pool_id), &contributions);
+        }
+
+        let total = env
+            .storage()
+            .instance()
+            .get::<_, u32>(&DataKey::MigratedPoolCount)
+            .unwrap_or(0);
+        env.storage()
+            .instance()
+            .set(&DataKey::MigratedPoolCount, &(total + migrated_pools));
+
+        migrated
+    }
+
+    // Mark the storage migration as finished once every pool is converted (admin only)
+    pub fn finish_migration(env: Env, admin: Address) {
+        // Verify admin
+        admin.require_auth();
+        require_admin(&env, &admin);
+
+        let migrated_pools = env
+            .storage()
+            .instance()
+            .get::<_, u32>(&DataKey::MigratedPoolCount)
+            .unwrap_or(0);
+        if schema_version(&env) < CURRENT_SCHEMA_VERSION
+            && migrated_pools < legacy_pool_count(&env)
+        {
+            panic!("Pools not yet migrated");
+        }
+
+        env.storage()
+            .instance()
+            .set(&DataKey::SchemaVersion, &CURRENT_SCHEMA_VERSION);
+    }
+
+    // Get the storage schema version
+    pub fn get_schema_version(env: Env) -> u32 {
+        schema_version(&env)
+    }
+
     // Create a new family pool
     pub fn create_pool(
         env: Env,
@@ -108,6 +512,7 @@ impl FamilyPoolContract {
     ) -> BytesN<32> {
         // Verify creator
         creator.require_auth();
+        require_initialized(&env);
 
         // Verify withdrawal limit is positive
         if withdrawal_limit <= 0 {
@@ -140,6 +545,12 @@ impl FamilyPoolContract {
             withdrawal_limit,
             withdrawal_period,
             created_at: env.ledger().timestamp(),
+            approval_tiers: Vec::new(&env),
+            reserved: 0,
+            status: PoolStatus::Active,
+            close_votes: Vec::new(&env),
+            closed_at: 0,
+            goal: None,
         };
 
         // Store pool data
@@ -177,6 +588,11 @@ impl FamilyPoolContract {
             .instance()
             .set(&DataKey::UserPools(creator), &user_pools);
 
+        // Record how many old pools there are before counting a new one
+        if schema_version(&env) < CURRENT_SCHEMA_VERSION {
+            legacy_pool_count(&env);
+        }
+
         // Increment pool count
         env.storage()
             .instance()
@@ -203,6 +619,7 @@ impl FamilyPoolContract {
             .instance()
             .get::<_, FamilyPool>(&DataKey::Pool(pool_id.clone()))
             .expect("Pool not found");
+        require_active(&pool);
 
         // Get members
         let mut members = env
@@ -297,6 +714,7 @@ impl FamilyPoolContract {
         if members.len() == initial_len {
             panic!("Member not found");
         }
+        require_admin_remains(&members);
 
         env.storage()
             .instance()
@@ -317,110 +735,79 @@ impl FamilyPoolContract {
             
         env.storage()
             .instance()
-            .set(&DataKey::UserPools(member_to_remove), &user_pools);
+            .set(&DataKey::UserPools(member_to_remove.clone()), &user_pools);
+
+        // Close what the member still had open in the pool
+        end_membership(&env, &pool_id, &member_to_remove);
 
         true
     }
 
-    // Contribute to the pool
-    pub fn contribute(
+    // Change the role of a pool member
+    pub fn change_role(
         env: Env,
         pool_id: BytesN<32>,
-        contributor: Address,
-        amount: i128,
-    ) -> BytesN<32> {
-        // Verify contributor
-        contributor.require_auth();
-
-        // Verify amount is positive
-        if amount <= 0 {
-            panic!("Amount must be positive");
-        }
+        admin: Address,
+        member: Address,
+        role: MemberRole,
+    ) -> bool {
+        // Verify admin
+        admin.require_auth();
 
         // Get pool data
-        let mut pool = env
+        let pool = env
             .storage()
             .instance()
             .get::<_, FamilyPool>(&DataKey::Pool(pool_id.clone()))
             .expect("Pool not found");
 
         // Get members
-        let members = env
+        let mut members = env
             .storage()
             .instance()
             .get::<_, Vec<Member>>(&DataKey::PoolMembers(pool_id.clone()))
             .expect("Members not found");
 
-        // Check if contributor is a member
-        let is_member = members.iter().any(|m| m.address == contributor);
-        if !is_member {
-            panic!("Not a member of this pool");
+        // Check if admin is authorized
+        let admin_is_authorized = members.iter().any(|m| m.address == admin && m.role == MemberRole::Admin);
+        if !admin_is_authorized {
+            panic!("Not authorized to change roles");
         }
 
-        // Transfer tokens from contributor to contract
-        let token_client = token::Client::new(&env, &pool.token);
-        token_client.transfer(
-            &contributor,
-            &env.current_contract_address(),
-            &amount,
-        );
+        // The creator stays an admin until the pool is transferred
+        if member == pool.creator && role != MemberRole::Admin {
+            panic!("Pool creator must stay an admin");
+        }
 
-        // Generate a unique ID for the contribution
-        let contribution_id = env.crypto().sha256(
-            &(
-                contributor.clone(),
-                pool_id.clone(),
-                amount,
-                env.ledger().timestamp(),
-            )
-                .into_val(&env),
-        );
+        // Find the member
+        let member_index = members
+            .iter()
+            .position(|m| m.address == member)
+            .expect("Member not found") as u32;
 
-        // Create contribution record
-        let contribution = Contribution {
-            id: contribution_id.clone(),
-            contributor: contributor.clone(),
-            amount,
-            token: pool.token.clone(),
-            created_at: env.ledger().timestamp(),
-        };
+        let mut updated = members.get(member_index).unwrap();
+        updated.role = role;
+        members.set(member_index, updated);
+        require_admin_remains(&members);
 
-        // Update pool contributions
-        let mut contributions = env
-            .storage()
-            .instance()
-            .get::<_, Vec<Contribution>>(&DataKey::PoolContributions(pool_id.clone()))
-            .expect("Contributions not found");
-        contributions.push_back(contribution);
         env.storage()
             .instance()
-            .set(&DataKey::PoolContributions(pool_id.clone()), &contributions);
+            .set(&DataKey::PoolMembers(pool_id.clone()), &members);
 
-        // Update pool balance
-        pool.balance += amount;
-        env.storage()
-            .instance()
-            .set(&DataKey::Pool(pool_id.clone()), &pool);
+        // Recipients are not listed in their pools
+        if role == MemberRole::Recipient {
+            unindex_user_pool(&env, &member, &pool_id);
+        } else {
+            index_user_pool(&env, &member, &pool_id);
+        }
 
-        // Return the contribution ID
-        contribution_id
+        true
     }
 
-    // Request a withdrawal from the pool
-    pub fn request_withdrawal(
-        env: Env,
-        pool_id: BytesN<32>,
-        requester: Address,
-        recipient: Address,
-        amount: i128,
-    ) -> BytesN<32> {
-        // Verify requester
-        requester.require_auth();
-
-        // Verify amount is positive
-        if amount <= 0 {
-            panic!("Amount must be positive");
-        }
+    // Leave a pool
+    pub fn leave_pool(env: Env, pool_id: BytesN<32>, member: Address) -> bool {
+        // Verify member
+        member.require_auth();
 
         // Get pool data
         let pool = env
@@ -429,29 +816,296 @@ impl FamilyPoolContract {
             .get::<_, FamilyPool>(&DataKey::Pool(pool_id.clone()))
             .expect("Pool not found");
 
-        // Check if pool has sufficient balance
-        if pool.balance < amount {
-            panic!("Insufficient pool balance");
+        // The creator has to hand the pool over first
+        if member == pool.creator {
+            panic!("Pool creator must transfer the pool before leaving");
         }
 
         // Get members
-        let members = env
+        let mut members = env
             .storage()
             .instance()
             .get::<_, Vec<Member>>(&DataKey::PoolMembers(pool_id.clone()))
             .expect("Members not found");
 
-        // Check if requester is a member with appropriate role
-        let requester_role = members
+        // Find and remove the member
+        let member_index = members
             .iter()
-            .find(|m| m.address == requester)
-            .map(|m| m.role);
-
-        if requester_role.is_none() {
-            panic!("Not a member of this pool");
-        }
+            .position(|m| m.address == member)
+            .expect("Not a member of this pool") as u32;
+        members.remove(member_index);
+        require_admin_remains(&members);
 
-        let is_admin = requester_role == Some(MemberRole::Admin);
+        env.storage()
+            .instance()
+            .set(&DataKey::PoolMembers(pool_id.clone()), &members);
+
+        // Update user's pools
+        unindex_user_pool(&env, &member, &pool_id);
+
+        // Close what the member still had open in the pool
+        end_membership(&env, &pool_id, &member);
+
+        true
+    }
+
+    // Hand the pool over to another admin, who becomes its creator
+    pub fn transfer_creator(
+        env: Env,
+        pool_id: BytesN<32>,
+        creator: Address,
+        new_creator: Address,
+    ) -> bool {
+        // Verify creator
+        creator.require_auth();
+
+        // Get pool data
+        let mut pool = env
+            .storage()
+            .instance()
+            .get::<_, FamilyPool>(&DataKey::Pool(pool_id.clone()))
+            .expect("Pool not found");
+
+        if pool.creator != creator {
+            panic!("Only the pool creator can transfer the pool");
+        }
+
+        // Get members
+        let members = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Member>>(&DataKey::PoolMembers(pool_id.clone()))
+            .expect("Members not found");
+
+        // The new creator must already be an admin of the pool
+        if !members.iter().any(|m| m.address == new_creator && m.role == MemberRole::Admin) {
+            panic!("New creator must be a pool admin");
+        }
+
+        // Update pool creator
+        pool.creator = new_creator;
+        env.storage()
+            .instance()
+            .set(&DataKey::Pool(pool_id), &pool);
+
+        true
+    }
+
+    // Contribute to the pool
+    pub fn contribute(
+        env: Env,
+        pool_id: BytesN<32>,
+        contributor: Address,
+        amount: i128,
+    ) -> BytesN<32> {
+        // Verify contributor
+        contributor.require_auth();
+        require_initialized(&env);
+
+        // Verify amount is positive
+        if amount <= 0 {
+            panic!("Amount must be positive");
+        }
+
+        // Get pool data
+        let mut pool = env
+            .storage()
+            .instance()
+            .get::<_, FamilyPool>(&DataKey::Pool(pool_id.clone()))
+            .expect("Pool not found");
+        require_active(&pool);
+
+        // Get members
+        let members = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Member>>(&DataKey::PoolMembers(pool_id.clone()))
+            .expect("Members not found");
+
+        // Check if contributor is a member
+        let is_member = members.iter().any(|m| m.address == contributor);
+        if !is_member {
+            panic!("Not a member of this pool");
+        }
+
+        // Transfer tokens from contributor to contract
+        let token_client = token::Client::new(&env, &pool.token);
+        token_client.transfer(
+            &contributor,
+            &env.current_contract_address(),
+            &amount,
+        );
+
+        // Generate a unique ID for the contribution
+        let contribution_id = env.crypto().sha256(
+            &(
+                contributor.clone(),
+                pool_id.clone(),
+                amount,
+                env.ledger().timestamp(),
+            )
+                .into_val(&env),
+        );
+
+        // Create contribution record
+        let contribution = Contribution {
+            id: contribution_id.clone(),
+            contributor: contributor.clone(),
+            amount,
+            token: pool.token.clone(),
+            created_at: env.ledger().timestamp(),
+            reference: None,
+        };
+
+        // Update pool contributions
+        let mut contributions = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Contribution>>(&DataKey::PoolContributions(pool_id.clone()))
+            .expect("Contributions not found");
+        contributions.push_back(contribution);
+        env.storage()
+            .instance()
+            .set(&DataKey::PoolContributions(pool_id.clone()), &contributions);
+
+        // Update pool balance
+        pool.balance += amount;
+        env.storage()
+            .instance()
+            .set(&DataKey::Pool(pool_id.clone()), &pool);
+
+        // Return the contribution ID
+        contribution_id
+    }
+
+    // Deposit a completed remittance into the pool on behalf of a member
+    // The depositor (the remittance contract) pays the tokens and the remittance ID
+    // is recorded as the contribution reference
+    pub fn deposit_remittance(
+        env: Env,
+        pool_id: BytesN<32>,
+        depositor: Address,
+        contributor: Address,
+        amount: i128,
+        reference: BytesN<32>,
+    ) -> BytesN<32> {
+        // Verify depositor
+        depositor.require_auth();
+        require_initialized(&env);
+
+        // Verify amount is positive
+        if amount <= 0 {
+            panic!("Amount must be positive");
+        }
+
+        // Get pool data
+        let mut pool = env
+            .storage()
+            .instance()
+            .get::<_, FamilyPool>(&DataKey::Pool(pool_id.clone()))
+            .expect("Pool not found");
+        require_active(&pool);
+
+        // Get members
+        let members = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Member>>(&DataKey::PoolMembers(pool_id.clone()))
+            .expect("Members not found");
+
+        // Check if contributor is a member
+        let is_member = members.iter().any(|m| m.address == contributor);
+        if !is_member {
+            panic!("Not a member of this pool");
+        }
+
+        // Transfer tokens from depositor to contract
+        let token_client = token::Client::new(&env, &pool.token);
+        token_client.transfer(
+            &depositor,
+            &env.current_contract_address(),
+            &amount,
+        );
+
+        // Create contribution record
+        let contribution = Contribution {
+            id: reference.clone(),
+            contributor: contributor.clone(),
+            amount,
+            token: pool.token.clone(),
+            created_at: env.ledger().timestamp(),
+            reference: Some(reference.clone()),
+        };
+
+        // Update pool contributions
+        let mut contributions = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Contribution>>(&DataKey::PoolContributions(pool_id.clone()))
+            .expect("Contributions not found");
+        contributions.push_back(contribution);
+        env.storage()
+            .instance()
+            .set(&DataKey::PoolContributions(pool_id.clone()), &contributions);
+
+        // Update pool balance
+        pool.balance += amount;
+        env.storage()
+            .instance()
+            .set(&DataKey::Pool(pool_id.clone()), &pool);
+
+        // Return the contribution ID
+        reference
+    }
+
+    // Request a withdrawal from the pool
+    pub fn request_withdrawal(
+        env: Env,
+        pool_id: BytesN<32>,
+        requester: Address,
+        recipient: Address,
+        amount: i128,
+    ) -> BytesN<32> {
+        // Verify requester
+        requester.require_auth();
+        require_initialized(&env);
+
+        // Verify amount is positive
+        if amount <= 0 {
+            panic!("Amount must be positive");
+        }
+
+        // Get pool data
+        let mut pool = env
+            .storage()
+            .instance()
+            .get::<_, FamilyPool>(&DataKey::Pool(pool_id.clone()))
+            .expect("Pool not found");
+        require_active(&pool);
+
+        // Check if pool has sufficient balance not locked by other pending withdrawals
+        if pool.balance - pool.reserved < amount {
+            panic!("Insufficient available balance");
+        }
+
+        // Get members
+        let members = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Member>>(&DataKey::PoolMembers(pool_id.clone()))
+            .expect("Members not found");
+
+        // Check if requester is a member with appropriate role
+        let requester_role = members
+            .iter()
+            .find(|m| m.address == requester)
+            .map(|m| m.role);
+
+        if requester_role.is_none() {
+            panic!("Not a member of this pool");
+        }
+
+        let is_admin = requester_role == Some(MemberRole::Admin);
         let is_recipient_valid = members.iter().any(|m| m.address == recipient && m.role == MemberRole::Recipient);
 
         // Only admins can request withdrawals for recipients
@@ -464,11 +1118,23 @@ impl FamilyPoolContract {
             panic!("Invalid recipient");
         }
 
-        // Check withdrawal limit
-        if amount > pool.withdrawal_limit {
+        // Check the recipient's cumulative withdrawal limit for the current period
+        let mut withdrawals = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Withdrawal>>(&DataKey::PoolWithdrawals(pool_id.clone()))
+            .expect("Withdrawals not found");
+        if amount > remaining_allowance(&env, &pool, &withdrawals, &recipient) {
             panic!("Amount exceeds withdrawal limit");
         }
 
+        // Check if the savings goal locks withdrawals
+        if let Some(progress) = goal_progress(&env, &pool) {
+            if progress.withdrawals_locked {
+                panic!("Withdrawals are locked until the savings goal is reached");
+            }
+        }
+
         // Generate a unique ID for the withdrawal
         let withdrawal_id = env.crypto().sha256(
             &(
@@ -488,29 +1154,31 @@ impl FamilyPoolContract {
             recipient: recipient.clone(),
             amount,
             token: pool.token.clone(),
-            status: if is_admin {
-                WithdrawalStatus::Approved
-            } else {
-                WithdrawalStatus::Pending
-            },
+            status: WithdrawalStatus::Pending,
             created_at: env.ledger().timestamp(),
             completed_at: 0,
+            approvals: Vec::new(&env),
+            rejections: Vec::new(&env),
+            approved_at: 0,
+            expires_at: env.ledger().timestamp() + WITHDRAWAL_EXPIRY,
         };
 
         // Update pool withdrawals
-        let mut withdrawals = env
-            .storage()
-            .instance()
-            .get::<_, Vec<Withdrawal>>(&DataKey::PoolWithdrawals(pool_id.clone()))
-            .expect("Withdrawals not found");
         withdrawals.push_back(withdrawal);
         env.storage()
             .instance()
             .set(&DataKey::PoolWithdrawals(pool_id.clone()), &withdrawals);
 
-        // If admin requested, process the withdrawal immediately
+        // Reserve the amount until the withdrawal is settled
+        pool.reserved += amount;
+        env.storage()
+            .instance()
+            .set(&DataKey::Pool(pool_id.clone()), &pool);
+
+        // If admin requested, count the request as their approval
+        // The withdrawal is paid out immediately when it needs no other approvals
         if is_admin {
-            Self::process_withdrawal(env, pool_id, withdrawal_id, requester, true);
+            vote_withdrawal(&env, &pool_id, &withdrawal_id, &requester, true);
         }
 
         // Return the withdrawal ID
@@ -528,12 +1196,917 @@ impl FamilyPoolContract {
         // Verify admin
         admin.require_auth();
 
+        vote_withdrawal(&env, &pool_id, &withdrawal_id, &admin, approve);
+
+        true
+    }
+
+    // Approve a withdrawal request; it is paid out once enough admins approve
+    pub fn approve_withdrawal(
+        env: Env,
+        pool_id: BytesN<32>,
+        withdrawal_id: BytesN<32>,
+        admin: Address,
+    ) -> WithdrawalStatus {
+        // Verify admin
+        admin.require_auth();
+
+        vote_withdrawal(&env, &pool_id, &withdrawal_id, &admin, true)
+    }
+
+    // Reject a withdrawal request; it is rejected once the approval threshold can no longer be met
+    pub fn reject_withdrawal(
+        env: Env,
+        pool_id: BytesN<32>,
+        withdrawal_id: BytesN<32>,
+        admin: Address,
+    ) -> WithdrawalStatus {
+        // Verify admin
+        admin.require_auth();
+
+        vote_withdrawal(&env, &pool_id, &withdrawal_id, &admin, false)
+    }
+
+    // Pay out an approved withdrawal that was waiting for the recipient's withdrawal limit or the savings goal
+    pub fn complete_withdrawal(env: Env, pool_id: BytesN<32>, withdrawal_id: BytesN<32>) -> bool {
+        // Get pool data
+        let mut pool = env
+            .storage()
+            .instance()
+            .get::<_, FamilyPool>(&DataKey::Pool(pool_id.clone()))
+            .expect("Pool not found");
+
+        // Get withdrawals
+        let mut withdrawals = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Withdrawal>>(&DataKey::PoolWithdrawals(pool_id.clone()))
+            .expect("Withdrawals not found");
+
+        // Find the withdrawal
+        let withdrawal_index = withdrawals
+            .iter()
+            .position(|w| w.id == withdrawal_id)
+            .expect("Withdrawal not found") as u32;
+
+        let mut withdrawal = withdrawals.get(withdrawal_index).unwrap();
+
+        // Check if withdrawal is approved and still open
+        if withdrawal.status != WithdrawalStatus::Approved {
+            panic!("Withdrawal is not approved");
+        }
+        if env.ledger().timestamp() >= withdrawal.expires_at {
+            panic!("Withdrawal has expired");
+        }
+
+        if let Some(progress) = goal_progress(&env, &pool) {
+            if progress.withdrawals_locked {
+                panic!("Withdrawals are locked until the savings goal is reached");
+            }
+        }
+        if !pay_withdrawal(&env, &mut pool, &withdrawals, &mut withdrawal) {
+            panic!("Amount exceeds withdrawal limit");
+        }
+
+        // Update pool and withdrawals
+        env.storage()
+            .instance()
+            .set(&DataKey::Pool(pool_id.clone()), &pool);
+        withdrawals.set(withdrawal_index, withdrawal);
+        env.storage()
+            .instance()
+            .set(&DataKey::PoolWithdrawals(pool_id.clone()), &withdrawals);
+
+        true
+    }
+
+    // Cancel an open withdrawal request (requester only)
+    pub fn cancel_withdrawal(
+        env: Env,
+        pool_id: BytesN<32>,
+        withdrawal_id: BytesN<32>,
+        requester: Address,
+    ) -> bool {
+        // Verify requester
+        requester.require_auth();
+
+        // Get pool data
+        let mut pool = env
+            .storage()
+            .instance()
+            .get::<_, FamilyPool>(&DataKey::Pool(pool_id.clone()))
+            .expect("Pool not found");
+
+        // Get withdrawals
+        let mut withdrawals = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Withdrawal>>(&DataKey::PoolWithdrawals(pool_id.clone()))
+            .expect("Withdrawals not found");
+
+        // Find the withdrawal
+        let withdrawal_index = withdrawals
+            .iter()
+            .position(|w| w.id == withdrawal_id)
+            .expect("Withdrawal not found") as u32;
+
+        let mut withdrawal = withdrawals.get(withdrawal_index).unwrap();
+
+        if withdrawal.requester != requester {
+            panic!("Not authorized to cancel this withdrawal");
+        }
+
+        // Check if withdrawal is still open
+        if withdrawal.status != WithdrawalStatus::Pending && withdrawal.status != WithdrawalStatus::Approved {
+            panic!("Withdrawal is not open");
+        }
+
+        // Update withdrawal status and release the reservation
+        withdrawal.status = WithdrawalStatus::Cancelled;
+        withdrawal.completed_at = env.ledger().timestamp();
+        pool.reserved -= withdrawal.amount;
+
+        // Update pool and withdrawals
+        env.storage()
+            .instance()
+            .set(&DataKey::Pool(pool_id.clone()), &pool);
+        withdrawals.set(withdrawal_index, withdrawal);
+        env.storage()
+            .instance()
+            .set(&DataKey::PoolWithdrawals(pool_id.clone()), &withdrawals);
+
+        true
+    }
+
+    // Expire the open withdrawals of a pool that passed their expiry time and release their reservations
+    // Anyone can call this. Returns the number of expired withdrawals.
+    pub fn expire_withdrawals(env: Env, pool_id: BytesN<32>) -> u32 {
+        // Get pool data
+        let mut pool = env
+            .storage()
+            .instance()
+            .get::<_, FamilyPool>(&DataKey::Pool(pool_id.clone()))
+            .expect("Pool not found");
+
+        // Get withdrawals
+        let withdrawals = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Withdrawal>>(&DataKey::PoolWithdrawals(pool_id.clone()))
+            .expect("Withdrawals not found");
+
+        let now = env.ledger().timestamp();
+        let mut expired = 0u32;
+        let mut updated = Vec::<Withdrawal>::new(&env);
+        for mut w in withdrawals.iter() {
+            let open = w.status == WithdrawalStatus::Pending || w.status == WithdrawalStatus::Approved;
+            if open && now >= w.expires_at {
+                w.status = WithdrawalStatus::Expired;
+                w.completed_at = now;
+                pool.reserved -= w.amount;
+                expired += 1;
+            }
+            updated.push_back(w);
+        }
+
+        if expired > 0 {
+            env.storage()
+                .instance()
+                .set(&DataKey::Pool(pool_id.clone()), &pool);
+            env.storage()
+                .instance()
+                .set(&DataKey::PoolWithdrawals(pool_id), &updated);
+        }
+
+        expired
+    }
+
+    // Set the number of admin approvals withdrawals need, by amount
+    // A withdrawal needs the approvals of the highest tier whose minimum amount it reaches,
+    // and a single approval when it reaches none
+    pub fn set_approval_tiers(
+        env: Env,
+        pool_id: BytesN<32>,
+        admin: Address,
+        tiers: Vec<ApprovalTier>,
+    ) -> bool {
+        // Verify admin
+        admin.require_auth();
+
+        // Get pool data
+        let mut pool = env
+            .storage()
+            .instance()
+            .get::<_, FamilyPool>(&DataKey::Pool(pool_id.clone()))
+            .expect("Pool not found");
+        require_active(&pool);
+
+        // Get members
+        let members = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Member>>(&DataKey::PoolMembers(pool_id.clone()))
+            .expect("Members not found");
+
+        // Check if admin is authorized
+        let admin_is_authorized = members.iter().any(|m| m.address == admin && m.role == MemberRole::Admin);
+        if !admin_is_authorized {
+            panic!("Not authorized to update approval tiers");
+        }
+
+        // Verify each tier can be met by the current admins
+        let admin_count = members.iter().filter(|m| m.role == MemberRole::Admin).count() as u32;
+        for tier in tiers.iter() {
+            if tier.min_amount < 0 {
+                panic!("Tier minimum amount must not be negative");
+            }
+            if tier.approvals == 0 || tier.approvals > admin_count {
+                panic!("Tier approvals must be between 1 and the number of admins");
+            }
+        }
+
+        // Update approval tiers
+        pool.approval_tiers = tiers;
+        env.storage()
+            .instance()
+            .set(&DataKey::Pool(pool_id.clone()), &pool);
+
+        true
+    }
+
+    // Start rotating savings in the pool (admin only)
+    // Admins and contributors take part, recipients do not
+    pub fn start_rosca(
+        env: Env,
+        pool_id: BytesN<32>,
+        admin: Address,
+        contribution: i128,
+        cycle_length: u64,
+        payout_order: PayoutOrder,
+    ) -> bool {
+        // Verify admin
+        admin.require_auth();
+
+        // Verify contribution and cycle length are positive
+        if contribution <= 0 {
+            panic!("Contribution must be positive");
+        }
+        if cycle_length == 0 {
+            panic!("Cycle length must be positive");
+        }
+
+        // Get pool data
+        let pool = env
+            .storage()
+            .instance()
+            .get::<_, FamilyPool>(&DataKey::Pool(pool_id.clone()))
+            .expect("Pool not found");
+        require_active(&pool);
+
+        if env.storage().instance().has(&DataKey::Rosca(pool_id.clone())) {
+            panic!("Rotating savings already started");
+        }
+
+        // Get members
+        let members = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Member>>(&DataKey::PoolMembers(pool_id.clone()))
+            .expect("Members not found");
+
+        // Check if admin is authorized
+        let admin_is_authorized = members.iter().any(|m| m.address == admin && m.role == MemberRole::Admin);
+        if !admin_is_authorized {
+            panic!("Not authorized to start rotating savings");
+        }
+
+        let mut order = Vec::<Address>::new(&env);
+        for m in members.iter() {
+            if m.role != MemberRole::Recipient {
+                order.push_back(m.address);
+            }
+        }
+        if order.len() < 2 {
+            panic!("Rotating savings needs at least two participants");
+        }
+        if payout_order == PayoutOrder::Random {
+            env.prng().shuffle(&mut order);
+        }
+
+        let rosca = Rosca {
+            contribution,
+            cycle_length,
+            payout_order,
+            order,
+            cycle: 0,
+            cycle_started_at: env.ledger().timestamp(),
+            contributed: Vec::new(&env),
+            paid: Vec::new(&env),
+            bids: Map::new(&env),
+            defaults: Map::new(&env),
+            payouts: Vec::new(&env),
+        };
+        env.storage()
+            .instance()
+            .set(&DataKey::Rosca(pool_id), &rosca);
+
+        true
+    }
+
+    // Pay the fixed contribution for the current rotating savings cycle
+    // The pot is paid out as soon as every participant has contributed
+    pub fn contribute_to_cycle(env: Env, pool_id: BytesN<32>, member: Address) -> BytesN<32> {
+        // Verify member
+        member.require_auth();
+        require_initialized(&env);
+
+        // Get pool data
+        let mut pool = env
+            .storage()
+            .instance()
+            .get::<_, FamilyPool>(&DataKey::Pool(pool_id.clone()))
+            .expect("Pool not found");
+        require_active(&pool);
+
+        let mut rosca = env
+            .storage()
+            .instance()
+            .get::<_, Rosca>(&DataKey::Rosca(pool_id.clone()))
+            .expect("Rotating savings not started");
+
+        if !rosca.order.contains(&member) {
+            panic!("Not a participant in rotating savings");
+        }
+        if rosca.contributed.contains(&member) {
+            panic!("Already contributed this cycle");
+        }
+        if env.ledger().timestamp() >= rosca.cycle_started_at + rosca.cycle_length {
+            panic!("Cycle has ended");
+        }
+
+        // Transfer tokens from member to contract
+        let token_client = token::Client::new(&env, &pool.token);
+        token_client.transfer(
+            &member,
+            &env.current_contract_address(),
+            &rosca.contribution,
+        );
+
+        // Generate a unique ID for the contribution
+        let contribution_id = env.crypto().sha256(
+            &(
+                member.clone(),
+                pool_id.clone(),
+                rosca.contribution,
+                env.ledger().timestamp(),
+            )
+                .to_xdr(&env),
+        );
+
+        // Update pool contributions
+        let mut contributions = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Contribution>>(&DataKey::PoolContributions(pool_id.clone()))
+            .expect("Contributions not found");
+        contributions.push_back(Contribution {
+            id: contribution_id.clone(),
+            contributor: member.clone(),
+            amount: rosca.contribution,
+            token: pool.token.clone(),
+            created_at: env.ledger().timestamp(),
+            reference: None,
+        });
+        env.storage()
+            .instance()
+            .set(&DataKey::PoolContributions(pool_id.clone()), &contributions);
+
+        // The pot is reserved for the cycle's beneficiary
+        pool.balance += rosca.contribution;
+        pool.reserved += rosca.contribution;
+        rosca.contributed.push_back(member);
+
+        if rosca.contributed.len() == rosca.order.len() {
+            let mut withdrawals = env
+                .storage()
+                .instance()
+                .get::<_, Vec<Withdrawal>>(&DataKey::PoolWithdrawals(pool_id.clone()))
+                .expect("Withdrawals not found");
+            pay_cycle(&env, &mut pool, &mut rosca, &mut withdrawals);
+            env.storage()
+                .instance()
+                .set(&DataKey::PoolWithdrawals(pool_id.clone()), &withdrawals);
+        }
+
+        env.storage()
+            .instance()
+            .set(&DataKey::Pool(pool_id.clone()), &pool);
+        env.storage()
+            .instance()
+            .set(&DataKey::Rosca(pool_id), &rosca);
+
+        // Return the contribution ID
+        contribution_id
+    }
+
+    // Bid for the pot of the current cycle by offering to leave `discount` of it in the pool
+    pub fn place_bid(env: Env, pool_id: BytesN<32>, member: Address, discount: i128) -> bool {
+        // Verify member
+        member.require_auth();
+
+        let mut rosca = env
+            .storage()
+            .instance()
+            .get::<_, Rosca>(&DataKey::Rosca(pool_id.clone()))
+            .expect("Rotating savings not started");
+
+        if rosca.payout_order != PayoutOrder::Bid {
+            panic!("Rotating savings does not take bids");
+        }
+        if !rosca.order.contains(&member) {
+            panic!("Not a participant in rotating savings");
+        }
+        if rosca.paid.contains(&member) {
+            panic!("Already received the pot this round");
+        }
+        if discount < 0 || discount >= rosca.contribution * rosca.order.len() as i128 {
+            panic!("Discount must be less than the pot");
+        }
+
+        rosca.bids.set(member, discount);
+        env.storage()
+            .instance()
+            .set(&DataKey::Rosca(pool_id), &rosca);
+
+        true
+    }
+
+    // Close a rotating savings cycle that ran out of time
+    // Participants who did not contribute are recorded as defaulting and the collected
+    // contributions are paid out. Anyone can call this.
+    pub fn close_cycle(env: Env, pool_id: BytesN<32>) -> bool {
+        // Get pool data
+        let mut pool = env
+            .storage()
+            .instance()
+            .get::<_, FamilyPool>(&DataKey::Pool(pool_id.clone()))
+            .expect("Pool not found");
+        require_active(&pool);
+
+        let mut rosca = env
+            .storage()
+            .instance()
+            .get::<_, Rosca>(&DataKey::Rosca(pool_id.clone()))
+            .expect("Rotating savings not started");
+
+        if env.ledger().timestamp() < rosca.cycle_started_at + rosca.cycle_length {
+            panic!("Cycle has not ended");
+        }
+
+        // Record defaults
+        for participant in rosca.order.iter() {
+            if !rosca.contributed.contains(&participant) {
+                let missed = rosca.defaults.get(participant.clone()).unwrap_or(0);
+                rosca.defaults.set(participant, missed + 1);
+            }
+        }
+
+        let mut withdrawals = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Withdrawal>>(&DataKey::PoolWithdrawals(pool_id.clone()))
+            .expect("Withdrawals not found");
+        pay_cycle(&env, &mut pool, &mut rosca, &mut withdrawals);
+        env.storage()
+            .instance()
+            .set(&DataKey::PoolWithdrawals(pool_id.clone()), &withdrawals);
+
+        env.storage()
+            .instance()
+            .set(&DataKey::Pool(pool_id.clone()), &pool);
+        env.storage()
+            .instance()
+            .set(&DataKey::Rosca(pool_id), &rosca);
+
+        true
+    }
+
+    // Schedule a recurring allowance from the pool to a member (admin only)
+    // The first payment is due immediately, then every `interval` seconds until `end_date`
+    pub fn create_allowance(
+        env: Env,
+        pool_id: BytesN<32>,
+        admin: Address,
+        recipient: Address,
+        amount: i128,
+        interval: u64,
+        end_date: u64,
+    ) -> u32 {
+        // Verify admin
+        admin.require_auth();
+
+        // Verify amount and interval are positive
+        if amount <= 0 {
+            panic!("Amount must be positive");
+        }
+        if interval < MIN_ALLOWANCE_INTERVAL {
+            panic!("Interval must be at least one day");
+        }
+        if end_date <= env.ledger().timestamp() {
+            panic!("End date must be in the future");
+        }
+
+        // Get pool data
+        let pool = env
+            .storage()
+            .instance()
+            .get::<_, FamilyPool>(&DataKey::Pool(pool_id.clone()))
+            .expect("Pool not found");
+        require_active(&pool);
+
+        // Allowances are capped by the pool's withdrawal limit, so a larger one could never be paid
+        if amount > pool.withdrawal_limit {
+            panic!("Amount exceeds withdrawal limit");
+        }
+
+        // Get members
+        let members = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Member>>(&DataKey::PoolMembers(pool_id.clone()))
+            .expect("Members not found");
+
+        // Check if admin is authorized
+        let admin_is_authorized = members.iter().any(|m| m.address == admin && m.role == MemberRole::Admin);
+        if !admin_is_authorized {
+            panic!("Not authorized to create allowances");
+        }
+
+        // Check if recipient is a member
+        if !members.iter().any(|m| m.address == recipient) {
+            panic!("Invalid recipient");
+        }
+
+        let mut allowances = env
+            .storage()
+            .instance()
+            .get::<_, Vec<AllowanceSchedule>>(&DataKey::Allowances(pool_id.clone()))
+            .unwrap_or_else(|| Vec::new(&env));
+
+        let id = allowances.len();
+        allowances.push_back(AllowanceSchedule {
+            id,
+            recipient,
+            amount,
+            interval,
+            next_due: env.ledger().timestamp(),
+            end_date,
+            created_by: admin,
+            active: true,
+        });
+        env.storage()
+            .instance()
+            .set(&DataKey::Allowances(pool_id), &allowances);
+
+        id
+    }
+
+    // Stop a recurring allowance (admin only)
+    pub fn cancel_allowance(env: Env, pool_id: BytesN<32>, admin: Address, allowance_id: u32) -> bool {
+        // Verify admin
+        admin.require_auth();
+
+        // Get members
+        let members = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Member>>(&DataKey::PoolMembers(pool_id.clone()))
+            .expect("Members not found");
+
+        // Check if admin is authorized
+        let admin_is_authorized = members.iter().any(|m| m.address == admin && m.role == MemberRole::Admin);
+        if !admin_is_authorized {
+            panic!("Not authorized to cancel allowances");
+        }
+
+        let mut allowances = env
+            .storage()
+            .instance()
+            .get::<_, Vec<AllowanceSchedule>>(&DataKey::Allowances(pool_id.clone()))
+            .unwrap_or_else(|| Vec::new(&env));
+
+        let mut allowance = allowances.get(allowance_id).expect("Allowance not found");
+        allowance.active = false;
+        allowances.set(allowance_id, allowance);
+        env.storage()
+            .instance()
+            .set(&DataKey::Allowances(pool_id), &allowances);
+
+        true
+    }
+
+    // Pay the allowances of the pool that are due, at most MAX_DISBURSEMENTS payments per call.
+    // Anyone can call this. Payments the available balance or the recipient's withdrawal limit
+    // cannot cover, or beyond the cap, stay due for a later call. Returns the total amount paid.
+    pub fn disburse_due(env: Env, pool_id: BytesN<32>) -> i128 {
+        // Get pool data
+        let mut pool = env
+            .storage()
+            .instance()
+            .get::<_, FamilyPool>(&DataKey::Pool(pool_id.clone()))
+            .expect("Pool not found");
+        require_active(&pool);
+
+        // A savings goal can lock allowances like any other withdrawal
+        if let Some(progress) = goal_progress(&env, &pool) {
+            if progress.withdrawals_locked {
+                return 0;
+            }
+        }
+
+        let mut allowances = env
+            .storage()
+            .instance()
+            .get::<_, Vec<AllowanceSchedule>>(&DataKey::Allowances(pool_id.clone()))
+            .unwrap_or_else(|| Vec::new(&env));
+
+        let mut withdrawals = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Withdrawal>>(&DataKey::PoolWithdrawals(pool_id.clone()))
+            .expect("Withdrawals not found");
+
+        let now = env.ledger().timestamp();
+        let token_client = token::Client::new(&env, &pool.token);
+        let mut total = 0i128;
+        let mut payments = 0u32;
+        for index in 0..allowances.len() {
+            let mut allowance = allowances.get(index).unwrap();
+
+            // Catch up on missed payments while they can be covered
+            while allowance.active && allowance.next_due <= now && payments < MAX_DISBURSEMENTS {
+                if allowance.next_due > allowance.end_date {
+                    allowance.active = false;
+                    break;
+                }
+                if pool.balance - pool.reserved < allowance.amount
+                    || allowance.amount > remaining_allowance(&env, &pool, &withdrawals, &allowance.recipient)
+                {
+                    break;
+                }
+
+                // Transfer tokens to recipient
+                token_client.transfer(
+                    &env.current_contract_address(),
+                    &allowance.recipient,
+                    &allowance.amount,
+                );
+                pool.balance -= allowance.amount;
+                total += allowance.amount;
+                payments += 1;
+
+                // Record the payment in the withdrawal history, where it counts towards the limit
+                let withdrawal_id = env.crypto().sha256(
+                    &(
+                        pool_id.clone(),
+                        allowance.id,
+                        allowance.recipient.clone(),
+                        allowance.next_due,
+                    )
+                        .to_xdr(&env),
+                );
+                withdrawals.push_back(Withdrawal {
+                    id: withdrawal_id,
+                    requester: allowance.created_by.clone(),
+                    recipient: allowance.recipient.clone(),
+                    amount: allowance.amount,
+                    token: pool.token.clone(),
+                    status: WithdrawalStatus::Completed,
+                    created_at: now,
+                    completed_at: now,
+                    approvals: Vec::from_array(&env, [allowance.created_by.clone()]),
+                    rejections: Vec::new(&env),
+                    approved_at: now,
+                    expires_at: now,
+                });
+
+                allowance.next_due += allowance.interval;
+            }
+
+            allowances.set(index, allowance);
+        }
+
+        env.storage()
+            .instance()
+            .set(&DataKey::Allowances(pool_id.clone()), &allowances);
+        if total > 0 {
+            env.storage()
+                .instance()
+                .set(&DataKey::PoolWithdrawals(pool_id.clone()), &withdrawals);
+            env.storage()
+                .instance()
+                .set(&DataKey::Pool(pool_id), &pool);
+        }
+
+        total
+    }
+
+    // Pledge to contribute `amount` to the pool every period, replacing any previous pledge
+    // The period in progress under the previous pledge stays owed at its amount
+    pub fn pledge(
+        env: Env,
+        pool_id: BytesN<32>,
+        member: Address,
+        amount: i128,
+        period: WithdrawalPeriod,
+    ) -> bool {
+        // Verify member
+        member.require_auth();
+
+        // Verify amount is positive
+        if amount <= 0 {
+            panic!("Amount must be positive");
+        }
+
+        // Get pool data
+        let pool = env
+            .storage()
+            .instance()
+            .get::<_, FamilyPool>(&DataKey::Pool(pool_id.clone()))
+            .expect("Pool not found");
+        require_active(&pool);
+
+        // Get members
+        let members = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Member>>(&DataKey::PoolMembers(pool_id.clone()))
+            .expect("Members not found");
+
+        // Check if member is a member
+        if !members.iter().any(|m| m.address == member) {
+            panic!("Not a member of this pool");
+        }
+
+        let pledges = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Pledge>>(&DataKey::Pledges(pool_id.clone()))
+            .unwrap_or_else(|| Vec::new(&env));
+
+        // End the member's previous pledge
+        let now = env.ledger().timestamp();
+        let mut updated = Vec::<Pledge>::new(&env);
+        for mut p in pledges.iter() {
+            if p.member == member && p.end == 0 {
+                p.end = now;
+            }
+            updated.push_back(p);
+        }
+        updated.push_back(Pledge {
+            member,
+            amount,
+            period,
+            start: now,
+            end: 0,
+        });
+        env.storage()
+            .instance()
+            .set(&DataKey::Pledges(pool_id), &updated);
+
+        true
+    }
+
+    // End a pledge; the period in progress and earlier arrears stay owed
+    pub fn cancel_pledge(env: Env, pool_id: BytesN<32>, member: Address) -> bool {
+        // Verify member
+        member.require_auth();
+
+        let mut pledges = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Pledge>>(&DataKey::Pledges(pool_id.clone()))
+            .unwrap_or_else(|| Vec::new(&env));
+
+        let pledge_index = pledges
+            .iter()
+            .position(|p| p.member == member && p.end == 0)
+            .expect("Pledge not found") as u32;
+        let mut pledge = pledges.get(pledge_index).unwrap();
+        pledge.end = env.ledger().timestamp();
+        pledges.set(pledge_index, pledge);
+        env.storage()
+            .instance()
+            .set(&DataKey::Pledges(pool_id), &pledges);
+
+        true
+    }
+
+    // Set the savings goal of the pool, replacing any previous goal
+    pub fn set_goal(
+        env: Env,
+        pool_id: BytesN<32>,
+        admin: Address,
+        target: i128,
+        deadline: u64,
+        lock_withdrawals: bool,
+    ) -> bool {
+        // Verify admin
+        admin.require_auth();
+
+        // Verify target is positive
+        if target <= 0 {
+            panic!("Goal target must be positive");
+        }
+
+        // Verify deadline is in the future
+        if deadline <= env.ledger().timestamp() {
+            panic!("Goal deadline must be in the future");
+        }
+
+        // Get pool data
+        let mut pool = env
+            .storage()
+            .instance()
+            .get::<_, FamilyPool>(&DataKey::Pool(pool_id.clone()))
+            .expect("Pool not found");
+        require_active(&pool);
+
+        // Get members
+        let members = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Member>>(&DataKey::PoolMembers(pool_id.clone()))
+            .expect("Members not found");
+
+        // Check if admin is authorized
+        let admin_is_authorized = members.iter().any(|m| m.address == admin && m.role == MemberRole::Admin);
+        if !admin_is_authorized {
+            panic!("Not authorized to set the savings goal");
+        }
+
+        // Update savings goal
+        pool.goal = Some(SavingsGoal {
+            target,
+            deadline,
+            lock_withdrawals,
+            created_at: env.ledger().timestamp(),
+        });
+        env.storage()
+            .instance()
+            .set(&DataKey::Pool(pool_id), &pool);
+
+        true
+    }
+
+    // Remove the savings goal of the pool, unlocking withdrawals
+    pub fn clear_goal(env: Env, pool_id: BytesN<32>, admin: Address) -> bool {
+        // Verify admin
+        admin.require_auth();
+
+        // Get pool data
+        let mut pool = env
+            .storage()
+            .instance()
+            .get::<_, FamilyPool>(&DataKey::Pool(pool_id.clone()))
+            .expect("Pool not found");
+
+        // Get members
+        let members = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Member>>(&DataKey::PoolMembers(pool_id.clone()))
+            .expect("Members not found");
+
+        // Check if admin is authorized
+        let admin_is_authorized = members.iter().any(|m| m.address == admin && m.role == MemberRole::Admin);
+        if !admin_is_authorized {
+            panic!("Not authorized to clear the savings goal");
+        }
+
+        // Remove savings goal
+        pool.goal = None;
+        env.storage()
+            .instance()
+            .set(&DataKey::Pool(pool_id), &pool);
+
+        true
+    }
+
+    // Vote to close the pool (admin only)
+    // Once a majority of admins voted, open withdrawals are cancelled and the balance is refunded
+    // to contributors pro rata to their net contributions. Returns whether the pool was closed.
+    pub fn close_pool(env: Env, pool_id: BytesN<32>, admin: Address) -> bool {
+        // Verify admin
+        admin.require_auth();
+
         // Get pool data
         let mut pool = env
             .storage()
             .instance()
             .get::<_, FamilyPool>(&DataKey::Pool(pool_id.clone()))
             .expect("Pool not found");
+        require_active(&pool);
 
         // Get members
         let members = env
@@ -545,63 +2118,128 @@ impl FamilyPoolContract {
         // Check if admin is authorized
         let admin_is_authorized = members.iter().any(|m| m.address == admin && m.role == MemberRole::Admin);
         if !admin_is_authorized {
-            panic!("Not authorized to process withdrawals");
+            panic!("Not authorized to close the pool");
         }
 
-        // Get withdrawals
-        let mut withdrawals = env
+        if pool.close_votes.contains(&admin) {
+            panic!("Admin already voted to close the pool");
+        }
+        pool.close_votes.push_back(admin.clone());
+
+        // Only votes of current admins count towards the quorum
+        let admin_count = members.iter().filter(|m| m.role == MemberRole::Admin).count() as u32;
+        let votes = count_admin_votes(&members, &pool.close_votes);
+
+        if votes * 2 <= admin_count {
+            env.storage()
+                .instance()
+                .set(&DataKey::Pool(pool_id.clone()), &pool);
+            return false;
+        }
+
+        let now = env.ledger().timestamp();
+
+        // Cancel open withdrawals
+        let withdrawals = env
             .storage()
             .instance()
             .get::<_, Vec<Withdrawal>>(&DataKey::PoolWithdrawals(pool_id.clone()))
             .expect("Withdrawals not found");
 
-        // Find the withdrawal
-        let withdrawal_index = withdrawals
-            .iter()
-            .position(|w| w.id == withdrawal_id)
-            .expect("Withdrawal not found");
+        let mut updated = Vec::<Withdrawal>::new(&env);
+        for mut w in withdrawals.iter() {
+            if w.status == WithdrawalStatus::Pending || w.status == WithdrawalStatus::Approved {
+                w.status = WithdrawalStatus::Cancelled;
+                w.completed_at = now;
+            }
+            updated.push_back(w);
+        }
+        pool.reserved = 0;
 
-        let mut withdrawal = withdrawals.get(withdrawal_index).unwrap().clone();
+        // Net contribution of each contributor: contributed minus withdrawn to them
+        let contributions = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Contribution>>(&DataKey::PoolContributions(pool_id.clone()))
+            .expect("Contributions not found");
 
-        // Check if withdrawal is pending
-        if withdrawal.status != WithdrawalStatus::Pending {
-            panic!("Withdrawal is not pending");
+        let mut net = Map::<Address, i128>::new(&env);
+        for c in contributions.iter() {
+            let total = net.get(c.contributor.clone()).unwrap_or(0);
+            net.set(c.contributor, total + c.amount);
+        }
+        for w in updated.iter() {
+            if w.status == WithdrawalStatus::Completed {
+                if let Some(total) = net.get(w.recipient.clone()) {
+                    net.set(w.recipient, total - w.amount);
+                }
+            }
         }
 
-        if approve {
-            // Check if pool has sufficient balance
-            if pool.balance < withdrawal.amount {
-                panic!("Insufficient pool balance");
+        let mut shares = Map::<Address, i128>::new(&env);
+        let mut total_net = 0i128;
+        for (contributor, amount) in net.iter() {
+            if amount > 0 {
+                shares.set(contributor, amount);
+                total_net += amount;
             }
+        }
 
-            // Update withdrawal status
-            withdrawal.status = WithdrawalStatus::Approved;
-            withdrawal.completed_at = env.ledger().timestamp();
+        // Refund the balance, the last contributor also receives the rounding remainder
+        let token_client = token::Client::new(&env, &pool.token);
+        let mut remaining = pool.balance;
+        let mut index = 0u32;
+        for (contributor, amount) in shares.iter() {
+            index += 1;
+            let refund = if index == shares.len() {
+                remaining
+            } else {
+                pool.balance * amount / total_net
+            };
+            if refund <= 0 {
+                continue;
+            }
 
-            // Transfer tokens to recipient
-            let token_client = token::Client::new(&env, &pool.token);
-            token_client.transfer(
-                &env.current_contract_address(),
-                &withdrawal.recipient,
-                &withdrawal.amount,
+            token_client.transfer(&env.current_contract_address(), &contributor, &refund);
+            remaining -= refund;
+
+            // Record the refund in the withdrawal history
+            let refund_id = env.crypto().sha256(
+                &(
+                    pool_id.clone(),
+                    contributor.clone(),
+                    refund,
+                    now,
+                )
+                    .to_xdr(&env),
             );
-
-            // Update pool balance
-            pool.balance -= withdrawal.amount;
-            env.storage()
-                .instance()
-                .set(&DataKey::Pool(pool_id.clone()), &pool);
-        } else {
-            // Reject the withdrawal
-            withdrawal.status = WithdrawalStatus::Rejected;
-            withdrawal.completed_at = env.ledger().timestamp();
+            updated.push_back(Withdrawal {
+                id: refund_id,
+                requester: admin.clone(),
+                recipient: contributor,
+                amount: refund,
+                token: pool.token.clone(),
+                status: WithdrawalStatus::Completed,
+                created_at: now,
+                completed_at: now,
+                approvals: pool.close_votes.clone(),
+                rejections: Vec::new(&env),
+                approved_at: now,
+                expires_at: now,
+            });
         }
 
-        // Update withdrawals
-        withdrawals.set(withdrawal_index, withdrawal);
         env.storage()
             .instance()
-            .set(&DataKey::PoolWithdrawals(pool_id.clone()), &withdrawals);
+            .set(&DataKey::PoolWithdrawals(pool_id.clone()), &updated);
+
+        // Update pool status
+        pool.balance = remaining;
+        pool.status = PoolStatus::Closed;
+        pool.closed_at = now;
+        env.storage()
+            .instance()
+            .set(&DataKey::Pool(pool_id), &pool);
 
         true
     }
@@ -638,6 +2276,135 @@ impl FamilyPoolContract {
             .expect("Withdrawals not found")
     }
 
+    // Get how much a member can still withdraw in the current withdrawal period
+    pub fn get_remaining_allowance(env: Env, pool_id: BytesN<32>, member: Address) -> i128 {
+        let pool = env
+            .storage()
+            .instance()
+            .get::<_, FamilyPool>(&DataKey::Pool(pool_id.clone()))
+            .expect("Pool not found");
+        let withdrawals = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Withdrawal>>(&DataKey::PoolWithdrawals(pool_id))
+            .expect("Withdrawals not found");
+
+        remaining_allowance(&env, &pool, &withdrawals, &member)
+    }
+
+    // Get the pool balance not locked by open withdrawals
+    pub fn get_available_balance(env: Env, pool_id: BytesN<32>) -> i128 {
+        let pool = env
+            .storage()
+            .instance()
+            .get::<_, FamilyPool>(&DataKey::Pool(pool_id))
+            .expect("Pool not found");
+
+        pool.balance - pool.reserved
+    }
+
+    // Get the progress towards the pool's savings goal
+    pub fn get_goal_progress(env: Env, pool_id: BytesN<32>) -> GoalProgress {
+        let pool = env
+            .storage()
+            .instance()
+            .get::<_, FamilyPool>(&DataKey::Pool(pool_id))
+            .expect("Pool not found");
+
+        goal_progress(&env, &pool).expect("Pool has no savings goal")
+    }
+
+    // Get the rotating savings state of the pool
+    pub fn get_rosca(env: Env, pool_id: BytesN<32>) -> Rosca {
+        env.storage()
+            .instance()
+            .get::<_, Rosca>(&DataKey::Rosca(pool_id))
+            .expect("Rotating savings not started")
+    }
+
+    // Get the allowance schedules of the pool
+    pub fn get_allowances(env: Env, pool_id: BytesN<32>) -> Vec<AllowanceSchedule> {
+        env.storage()
+            .instance()
+            .get::<_, Vec<AllowanceSchedule>>(&DataKey::Allowances(pool_id))
+            .unwrap_or_else(|| Vec::new(&env))
+    }
+
+    // Get the pledges of the pool, including ended ones
+    pub fn get_pledges(env: Env, pool_id: BytesN<32>) -> Vec<Pledge> {
+        env.storage()
+            .instance()
+            .get::<_, Vec<Pledge>>(&DataKey::Pledges(pool_id))
+            .unwrap_or_else(|| Vec::new(&env))
+    }
+
+    // Get the pledge fulfilment of every member who pledged to the pool, across all their pledges
+    pub fn get_arrears(env: Env, pool_id: BytesN<32>) -> Vec<Arrears> {
+        let pledges = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Pledge>>(&DataKey::Pledges(pool_id.clone()))
+            .unwrap_or_else(|| Vec::new(&env));
+
+        let contributions = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Contribution>>(&DataKey::PoolContributions(pool_id))
+            .expect("Contributions not found");
+
+        let mut members = Vec::<Address>::new(&env);
+        let mut totals = Map::<Address, Arrears>::new(&env);
+        for pledge in pledges.iter() {
+            let part = pledge_arrears(&env, &pledge, &contributions);
+            let total = match totals.get(pledge.member.clone()) {
+                Some(mut total) => {
+                    total.periods += part.periods;
+                    total.missed_periods += part.missed_periods;
+                    total.outstanding += part.outstanding;
+                    if pledge.end == 0 {
+                        total.pledged = part.pledged;
+                        total.current_paid = part.current_paid;
+                    }
+                    total
+                }
+                None => {
+                    members.push_back(pledge.member.clone());
+                    part
+                }
+            };
+            totals.set(pledge.member, total);
+        }
+
+        let mut arrears = Vec::<Arrears>::new(&env);
+        for member in members.iter() {
+            arrears.push_back(totals.get(member).unwrap());
+        }
+
+        arrears
+    }
+
+    // Get the token a member deposits into the pool in
+    // Panics if the pool is closed or the contributor is not a member
+    pub fn get_deposit_token(env: Env, pool_id: BytesN<32>, contributor: Address) -> Address {
+        let pool = env
+            .storage()
+            .instance()
+            .get::<_, FamilyPool>(&DataKey::Pool(pool_id.clone()))
+            .expect("Pool not found");
+        require_active(&pool);
+
+        let members = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Member>>(&DataKey::PoolMembers(pool_id))
+            .expect("Members not found");
+        if !members.iter().any(|m| m.address == contributor) {
+            panic!("Not a member of this pool");
+        }
+
+        pool.token
+    }
+
     // Get user's pools
     pub fn get_user_pools(env: Env, user: Address) -> Vec<FamilyPool> {
         let pool_ids = env
@@ -742,3 +2509,600 @@ impl FamilyPoolContract {
         true
     }
 }
+
+// Check that the caller is the contract admin
+fn require_admin(env: &Env, admin: &Address) {
+    let stored_admin = env
+        .storage()
+        .instance()
+        .get::<_, Address>(&DataKey::Admin)
+        .expect("Contract not initialized");
+
+    if stored_admin != *admin {
+        panic!("Not authorized");
+    }
+}
+
+// Pools can only be created or funded once the contract has an admin
+fn require_initialized(env: &Env) {
+    if !env.storage().instance().has(&DataKey::Admin) {
+        panic!("Contract not initialized");
+    }
+}
+
+// Get the storage schema version; contracts deployed before versioning are version 1
+fn schema_version(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get::<_, u32>(&DataKey::SchemaVersion)
+        .unwrap_or(1)
+}
+
+// Number of pools created by the old code, recorded on the first call that needs it
+// while the storage is not yet migrated
+fn legacy_pool_count(env: &Env) -> u32 {
+    if let Some(count) = env
+        .storage()
+        .instance()
+        .get::<_, u32>(&DataKey::LegacyPoolCount)
+    {
+        return count;
+    }
+
+    let count = env
+        .storage()
+        .instance()
+        .get::<_, u32>(&DataKey::PoolCount)
+        .unwrap_or(0);
+    env.storage().instance().set(&DataKey::LegacyPoolCount, &count);
+    count
+}
+
+// Length of a withdrawal period in seconds
+fn period_seconds(period: WithdrawalPeriod) -> u64 {
+    match period {
+        WithdrawalPeriod::Daily => 86400,
+        WithdrawalPeriod::Weekly => 7 * 86400,
+        WithdrawalPeriod::Monthly => 30 * 86400,
+    }
+}
+
+// Amount a member can still withdraw within the rolling withdrawal period,
+// counting the withdrawals paid out to them during the window
+fn remaining_allowance(
+    env: &Env,
+    pool: &FamilyPool,
+    withdrawals: &Vec<Withdrawal>,
+    member: &Address,
+) -> i128 {
+    let now = env.ledger().timestamp();
+    let window_start = now.saturating_sub(period_seconds(pool.withdrawal_period));
+
+    // Rotating savings payouts and returned contributions are the member's own money
+    let mut withdrawn = 0i128;
+    for w in withdrawals.iter() {
+        if w.recipient == *member
+            && w.requester != env.current_contract_address()
+            && w.status == WithdrawalStatus::Completed
+            && w.completed_at > window_start
+        {
+            withdrawn += w.amount;
+        }
+    }
+
+    if withdrawn >= pool.withdrawal_limit {
+        0
+    } else {
+        pool.withdrawal_limit - withdrawn
+    }
+}
+
+// Pay the collected contributions of the current rotating savings cycle to its beneficiary
+// and start the next cycle. Only participants who contributed this cycle can receive the pot.
+// A cycle without contributions pays nobody, so nobody loses their turn, and contributions
+// are returned when every contributor already received the pot this round.
+// Payouts are recorded as completed withdrawals.
+fn pay_cycle(env: &Env, pool: &mut FamilyPool, rosca: &mut Rosca, withdrawals: &mut Vec<Withdrawal>) {
+    let collected = rosca.contribution * rosca.contributed.len() as i128;
+
+    if collected > 0 {
+        // Choose the beneficiary among contributors who have not received the pot this round
+        let mut beneficiary: Option<Address> = None;
+        let mut best_bid = -1i128;
+        for participant in rosca.order.iter() {
+            if rosca.paid.contains(&participant) || !rosca.contributed.contains(&participant) {
+                continue;
+            }
+            if rosca.payout_order != PayoutOrder::Bid {
+                beneficiary = Some(participant);
+                break;
+            }
+            // The largest bid wins, ties and no bids fall back to the participant order
+            let bid = rosca.bids.get(participant.clone()).unwrap_or(0);
+            if bid > best_bid {
+                best_bid = bid;
+                beneficiary = Some(participant);
+            }
+        }
+
+        match beneficiary {
+            Some(beneficiary) => {
+                let discount = if rosca.payout_order == PayoutOrder::Bid {
+                    rosca.bids.get(beneficiary.clone()).unwrap_or(0).min(collected)
+                } else {
+                    0
+                };
+                let amount = collected - discount;
+
+                // Transfer the pot to the beneficiary, the discount stays in the pool
+                pay_from_pool(env, pool, withdrawals, &beneficiary, amount, rosca.cycle);
+                pool.reserved -= collected;
+
+                rosca.payouts.push_back(RoscaPayout {
+                    cycle: rosca.cycle,
+                    beneficiary: beneficiary.clone(),
+                    amount,
+                    paid_at: env.ledger().timestamp(),
+                });
+                rosca.paid.push_back(beneficiary);
+
+                // Start a new round once everyone received the pot
+                if rosca.paid.len() == rosca.order.len() {
+                    rosca.paid = Vec::new(env);
+                    if rosca.payout_order == PayoutOrder::Random {
+                        env.prng().shuffle(&mut rosca.order);
+                    }
+                }
+            }
+            None => {
+                // Return the contributions
+                for contributor in rosca.contributed.iter() {
+                    pay_from_pool(env, pool, withdrawals, &contributor, rosca.contribution, rosca.cycle);
+                }
+                pool.reserved -= collected;
+            }
+        }
+    }
+
+    // Start the next cycle
+    rosca.cycle += 1;
+    rosca.cycle_started_at = env.ledger().timestamp();
+    rosca.contributed = Vec::new(env);
+    rosca.bids = Map::new(env);
+}
+
+// Pay a rotating savings payout or returned contribution from the pool balance
+// and record it as a completed withdrawal requested by the contract itself
+fn pay_from_pool(
+    env: &Env,
+    pool: &mut FamilyPool,
+    withdrawals: &mut Vec<Withdrawal>,
+    recipient: &Address,
+    amount: i128,
+    cycle: u32,
+) {
+    if amount <= 0 {
+        return;
+    }
+
+    let token_client = token::Client::new(env, &pool.token);
+    token_client.transfer(&env.current_contract_address(), recipient, &amount);
+    pool.balance -= amount;
+
+    let now = env.ledger().timestamp();
+    let withdrawal_id = env
+        .crypto()
+        .sha256(&(pool.id.clone(), cycle, recipient.clone(), now).to_xdr(env));
+    withdrawals.push_back(Withdrawal {
+        id: withdrawal_id,
+        requester: env.current_contract_address(),
+        recipient: recipient.clone(),
+        amount,
+        token: pool.token.clone(),
+        status: WithdrawalStatus::Completed,
+        created_at: now,
+        completed_at: now,
+        approvals: Vec::new(env),
+        rejections: Vec::new(env),
+        approved_at: now,
+        expires_at: now,
+    });
+}
+
+// Take a former member out of rotating savings, returning any contribution they made
+// this cycle, and pay the pot if everyone left has contributed
+fn leave_rosca(env: &Env, pool: &mut FamilyPool, withdrawals: &mut Vec<Withdrawal>, member: &Address) {
+    let key = DataKey::Rosca(pool.id.clone());
+    let mut rosca = match env.storage().instance().get::<_, Rosca>(&key) {
+        Some(rosca) => rosca,
+        None => return,
+    };
+    let index = match rosca.order.first_index_of(member) {
+        Some(index) => index,
+        None => return,
+    };
+    rosca.order.remove(index);
+
+    if let Some(index) = rosca.contributed.first_index_of(member) {
+        rosca.contributed.remove(index);
+        pay_from_pool(env, pool, withdrawals, member, rosca.contribution, rosca.cycle);
+        pool.reserved -= rosca.contribution;
+    }
+    if let Some(index) = rosca.paid.first_index_of(member) {
+        rosca.paid.remove(index);
+    }
+    rosca.bids.remove(member.clone());
+
+    if !rosca.order.is_empty() && rosca.contributed.len() == rosca.order.len() {
+        pay_cycle(env, pool, &mut rosca, withdrawals);
+    }
+
+    env.storage().instance().set(&key, &rosca);
+}
+
+// Fulfilment of a single pledge, grouping the member's contributions by pledge period
+// An ended pledge owes every period it started, including the one in progress when it ended
+fn pledge_arrears(env: &Env, pledge: &Pledge, contributions: &Vec<Contribution>) -> Arrears {
+    let length = period_seconds(pledge.period);
+    let current = if pledge.end == 0 {
+        ((env.ledger().timestamp() - pledge.start) / length) as u32
+    } else {
+        ((pledge.end - pledge.start + length - 1) / length) as u32
+    };
+    let end = if pledge.end == 0 { u64::MAX } else { pledge.end };
+
+    // Contributed amount per period index
+    let mut paid = Map::<u32, i128>::new(env);
+    for c in contributions.iter() {
+        if c.contributor == pledge.member && c.created_at >= pledge.start && c.created_at < end {
+            let index = ((c.created_at - pledge.start) / length) as u32;
+            let total = paid.get(index).unwrap_or(0);
+            paid.set(index, total + c.amount);
+        }
+    }
+
+    let mut missed_periods = 0u32;
+    let mut outstanding = 0i128;
+    for index in 0..current {
+        let contributed = paid.get(index).unwrap_or(0);
+        if contributed < pledge.amount {
+            missed_periods += 1;
+            outstanding += pledge.amount - contributed;
+        }
+    }
+
+    let in_force = pledge.end == 0;
+    Arrears {
+        member: pledge.member.clone(),
+        pledged: if in_force { pledge.amount } else { 0 },
+        periods: current,
+        missed_periods,
+        outstanding,
+        current_paid: if in_force { paid.get(current).unwrap_or(0) } else { 0 },
+    }
+}
+
+// Progress towards the pool's savings goal, if it has one
+fn goal_progress(env: &Env, pool: &FamilyPool) -> Option<GoalProgress> {
+    let goal = pool.goal.clone()?;
+
+    let contributions = env
+        .storage()
+        .instance()
+        .get::<_, Vec<Contribution>>(&DataKey::PoolContributions(pool.id.clone()))
+        .expect("Contributions not found");
+
+    let mut contributed = 0i128;
+    for c in contributions.iter() {
+        if c.created_at >= goal.created_at {
+            contributed += c.amount;
+        }
+    }
+
+    // Rotating savings contributions paid straight back out, as pots or returned
+    // contributions, are not savings
+    let withdrawals = env
+        .storage()
+        .instance()
+        .get::<_, Vec<Withdrawal>>(&DataKey::PoolWithdrawals(pool.id.clone()))
+        .expect("Withdrawals not found");
+    for w in withdrawals.iter() {
+        if w.requester == env.current_contract_address()
+            && w.status == WithdrawalStatus::Completed
+            && w.completed_at >= goal.created_at
+        {
+            contributed -= w.amount;
+        }
+    }
+
+    let reached = contributed >= goal.target;
+    let deadline_passed = env.ledger().timestamp() >= goal.deadline;
+    Some(GoalProgress {
+        target: goal.target,
+        contributed,
+        deadline: goal.deadline,
+        reached,
+        withdrawals_locked: goal.lock_withdrawals && !reached && !deadline_passed,
+    })
+}
+
+// Panic if the pool is closed
+fn require_active(pool: &FamilyPool) {
+    if pool.status != PoolStatus::Active {
+        panic!("Pool is closed");
+    }
+}
+
+// Panic unless the pool members include at least one admin
+fn require_admin_remains(members: &Vec<Member>) {
+    if !members.iter().any(|m| m.role == MemberRole::Admin) {
+        panic!("Pool must keep at least one admin");
+    }
+}
+
+// Cancel the open withdrawals a former member requested or would receive, releasing
+// their reservations, take them out of rotating savings, stop the allowances paid to them
+// and end their pledge, keeping its history
+fn end_membership(env: &Env, pool_id: &BytesN<32>, member: &Address) {
+    let mut pool = env
+        .storage()
+        .instance()
+        .get::<_, FamilyPool>(&DataKey::Pool(pool_id.clone()))
+        .expect("Pool not found");
+
+    let withdrawals = env
+        .storage()
+        .instance()
+        .get::<_, Vec<Withdrawal>>(&DataKey::PoolWithdrawals(pool_id.clone()))
+        .expect("Withdrawals not found");
+
+    let now = env.ledger().timestamp();
+    let mut updated = Vec::<Withdrawal>::new(env);
+    for mut w in withdrawals.iter() {
+        let open = w.status == WithdrawalStatus::Pending || w.status == WithdrawalStatus::Approved;
+        if open && (w.requester == *member || w.recipient == *member) {
+            w.status = WithdrawalStatus::Cancelled;
+            w.completed_at = now;
+            pool.reserved -= w.amount;
+        }
+        updated.push_back(w);
+    }
+    leave_rosca(env, &mut pool, &mut updated, member);
+    env.storage()
+        .instance()
+        .set(&DataKey::PoolWithdrawals(pool_id.clone()), &updated);
+    env.storage()
+        .instance()
+        .set(&DataKey::Pool(pool_id.clone()), &pool);
+
+    if let Some(allowances) = env
+        .storage()
+        .instance()
+        .get::<_, Vec<AllowanceSchedule>>(&DataKey::Allowances(pool_id.clone()))
+    {
+        let mut updated = Vec::<AllowanceSchedule>::new(env);
+        for mut a in allowances.iter() {
+            if a.recipient == *member {
+                a.active = false;
+            }
+            updated.push_back(a);
+        }
+        env.storage()
+            .instance()
+            .set(&DataKey::Allowances(pool_id.clone()), &updated);
+    }
+
+    if let Some(mut pledges) = env
+        .storage()
+        .instance()
+        .get::<_, Vec<Pledge>>(&DataKey::Pledges(pool_id.clone()))
+    {
+        if let Some(index) = pledges.iter().position(|p| p.member == *member && p.end == 0) {
+            let mut pledge = pledges.get(index as u32).unwrap();
+            pledge.end = now;
+            pledges.set(index as u32, pledge);
+            env.storage()
+                .instance()
+                .set(&DataKey::Pledges(pool_id.clone()), &pledges);
+        }
+    }
+}
+
+// Add a pool to a user's pools
+fn index_user_pool(env: &Env, user: &Address, pool_id: &BytesN<32>) {
+    let mut user_pools = env
+        .storage()
+        .instance()
+        .get::<_, Vec<BytesN<32>>>(&DataKey::UserPools(user.clone()))
+        .unwrap_or_else(|| Vec::new(env));
+
+    if !user_pools.contains(pool_id) {
+        user_pools.push_back(pool_id.clone());
+        env.storage()
+            .instance()
+            .set(&DataKey::UserPools(user.clone()), &user_pools);
+    }
+}
+
+// Remove a pool from a user's pools
+fn unindex_user_pool(env: &Env, user: &Address, pool_id: &BytesN<32>) {
+    let user_pools = env
+        .storage()
+        .instance()
+        .get::<_, Vec<BytesN<32>>>(&DataKey::UserPools(user.clone()))
+        .unwrap_or_else(|| Vec::new(env));
+
+    let mut remaining = Vec::<BytesN<32>>::new(env);
+    for p in user_pools.iter() {
+        if p != *pool_id {
+            remaining.push_back(p);
+        }
+    }
+    env.storage()
+        .instance()
+        .set(&DataKey::UserPools(user.clone()), &remaining);
+}
+
+// Number of admin approvals a withdrawal of `amount` needs under the pool's tiers
+fn required_approvals(pool: &FamilyPool, amount: i128) -> u32 {
+    let mut required = 1;
+    let mut best_min = -1;
+    for tier in pool.approval_tiers.iter() {
+        if amount >= tier.min_amount && tier.min_amount > best_min {
+            required = tier.approvals;
+            best_min = tier.min_amount;
+        }
+    }
+    required
+}
+
+// Pay out an approved withdrawal if the recipient's withdrawal limit and the savings goal allow it
+// Both are checked at payout, other withdrawals may have completed or a goal may have been set since the request
+fn pay_withdrawal(
+    env: &Env,
+    pool: &mut FamilyPool,
+    withdrawals: &Vec<Withdrawal>,
+    withdrawal: &mut Withdrawal,
+) -> bool {
+    if let Some(progress) = goal_progress(env, pool) {
+        if progress.withdrawals_locked {
+            return false;
+        }
+    }
+    if withdrawal.amount > remaining_allowance(env, pool, withdrawals, &withdrawal.recipient) {
+        return false;
+    }
+
+    // Check if pool has sufficient balance
+    if pool.balance < withdrawal.amount {
+        panic!("Insufficient pool balance");
+    }
+
+    // Transfer tokens to recipient
+    let token_client = token::Client::new(env, &pool.token);
+    token_client.transfer(
+        &env.current_contract_address(),
+        &withdrawal.recipient,
+        &withdrawal.amount,
+    );
+
+    // Update pool balance and release the reservation
+    pool.balance -= withdrawal.amount;
+    pool.reserved -= withdrawal.amount;
+
+    // Update withdrawal status
+    withdrawal.status = WithdrawalStatus::Completed;
+    withdrawal.completed_at = env.ledger().timestamp();
+
+    true
+}
+
+// Number of votes cast by members who are still admins
+fn count_admin_votes(members: &Vec<Member>, votes: &Vec<Address>) -> u32 {
+    votes
+        .iter()
+        .filter(|v| members.iter().any(|m| m.address == *v && m.role == MemberRole::Admin))
+        .count() as u32
+}
+
+// Record an admin's approval or rejection of a pending withdrawal, approving and paying it out
+// once the approval threshold is met or rejecting it once the threshold can no longer be met
+fn vote_withdrawal(
+    env: &Env,
+    pool_id: &BytesN<32>,
+    withdrawal_id: &BytesN<32>,
+    admin: &Address,
+    approve: bool,
+) -> WithdrawalStatus {
+    // Get pool data
+    let mut pool = env
+        .storage()
+        .instance()
+        .get::<_, FamilyPool>(&DataKey::Pool(pool_id.clone()))
+        .expect("Pool not found");
+
+    // Get members
+    let members = env
+        .storage()
+        .instance()
+        .get::<_, Vec<Member>>(&DataKey::PoolMembers(pool_id.clone()))
+        .expect("Members not found");
+
+    // Check if admin is authorized
+    let admin_is_authorized = members.iter().any(|m| m.address == *admin && m.role == MemberRole::Admin);
+    if !admin_is_authorized {
+        panic!("Not authorized to process withdrawals");
+    }
+
+    // Get withdrawals
+    let mut withdrawals = env
+        .storage()
+        .instance()
+        .get::<_, Vec<Withdrawal>>(&DataKey::PoolWithdrawals(pool_id.clone()))
+        .expect("Withdrawals not found");
+
+    // Find the withdrawal
+    let withdrawal_index = withdrawals
+        .iter()
+        .position(|w| w.id == *withdrawal_id)
+        .expect("Withdrawal not found") as u32;
+
+    let mut withdrawal = withdrawals.get(withdrawal_index).unwrap();
+
+    // Check if withdrawal is pending
+    if withdrawal.status != WithdrawalStatus::Pending {
+        panic!("Withdrawal is not pending");
+    }
+    if env.ledger().timestamp() >= withdrawal.expires_at {
+        panic!("Withdrawal has expired");
+    }
+
+    // Each admin votes once
+    if withdrawal.approvals.contains(admin) || withdrawal.rejections.contains(admin) {
+        panic!("Admin already voted on this withdrawal");
+    }
+
+    // Removed admins could otherwise make the threshold unreachable
+    let admin_count = members.iter().filter(|m| m.role == MemberRole::Admin).count() as u32;
+    let required = required_approvals(&pool, withdrawal.amount).min(admin_count);
+
+    if approve {
+        withdrawal.approvals.push_back(admin.clone());
+
+        if count_admin_votes(&members, &withdrawal.approvals) >= required {
+            withdrawal.status = WithdrawalStatus::Approved;
+            withdrawal.approved_at = env.ledger().timestamp();
+
+            // Pay out now if the recipient's withdrawal limit and the savings goal allow it,
+            // otherwise the withdrawal stays approved until complete_withdrawal
+            if pay_withdrawal(env, &mut pool, &withdrawals, &mut withdrawal) {
+                env.storage()
+                    .instance()
+                    .set(&DataKey::Pool(pool_id.clone()), &pool);
+            }
+        }
+    } else {
+        withdrawal.rejections.push_back(admin.clone());
+
+        // Reject the withdrawal once too few admins are left to approve it
+        if admin_count.saturating_sub(count_admin_votes(&members, &withdrawal.rejections)) < required {
+            withdrawal.status = WithdrawalStatus::Rejected;
+            withdrawal.completed_at = env.ledger().timestamp();
+
+            // Release the reservation
+            pool.reserved -= withdrawal.amount;
+            env.storage()
+                .instance()
+                .set(&DataKey::Pool(pool_id.clone()), &pool);
+        }
+    }
+
+    // Update withdrawals
+    let status = withdrawal.status;
+    withdrawals.set(withdrawal_index, withdrawal);
+    env.storage()
+        .instance()
+        .set(&DataKey::PoolWithdrawals(pool_id.clone()), &withdrawals);
+
+    status
+}
@@ -1,6 +1,55 @@
 #![no_std]
-use soroban_sdk::{contract, contractimpl, contracttype, Address, BytesN, Bytes, Env, String, Vec};
+use soroban_sdk::{
+    contract, contractclient, contractimpl, contracttype, token, Address, BytesN, Bytes, Env,
+    IntoVal, Map, String, Symbol, TryFromVal, Val, Vec,
+};
+use soroban_sdk::auth::{ContractContext, InvokerContractAuthEntry, SubContractInvocation};
 use soroban_sdk::vec;
+use soroban_sdk::xdr::ToXdr;
+
+// Family pool contract entry point used to deposit remittances into a pool
+#[contractclient(name = "FamilyPoolClient")]
+pub trait FamilyPool {
+    fn deposit_remittance(
+        env: Env,
+        pool_id: BytesN<32>,
+        depositor: Address,
+        contributor: Address,
+        amount: i128,
+        reference: BytesN<32>,
+    ) -> BytesN<32>;
+
+    fn get_deposit_token(env: Env, pool_id: BytesN<32>, contributor: Address) -> Address;
+}
+
+// Storage schema version written by this code
+// Version 1 is the original layout without escrow, operators or hashed recipients
+const CURRENT_SCHEMA_VERSION: u32 = 2;
+
+// Maximum number of records converted by a single migrate call
+const MAX_MIGRATION_BATCH: u32 = 25;
+
+// Number of times a remittance can be escalated before it is refunded
+const MAX_ESCALATIONS: u32 = 3;
+
+// Number of times a sender can amend the recipient of a remittance
+const MAX_AMENDMENTS: u32 = 3;
+
+// Maximum length of a recipient identifier (E.164 phone number, IBAN or wallet address)
+const MAX_IDENTIFIER_LEN: usize = 64;
+
+// Maximum length of a remittance memo
+const MAX_MEMO_LEN: u32 = 140;
+
+// Delay (in seconds) before a change of a remittance's refund address takes effect
+const REFUND_ADDRESS_DELAY: u64 = 86400;
+
+// Maximum relayer fee a claim voucher can pay, in basis points of the remittance amount
+const MAX_RELAYER_FEE_BPS: i128 = 200;
+
+// Default seconds after completion during which an operator's bond stays locked for the payout,
+// so non-payment can still be proven and slashed (7 days)
+const DEFAULT_CHALLENGE_PERIOD: u64 = 7 * 24 * 60 * 60;
 
 // Status of a remittance
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -9,12 +58,131 @@ pub enum RemittanceStatus {
     Pending,
     Completed,
     Cancelled,
+    Refunded,
+    Delivered,
+    Disputed,
+    CancelRequested, // Cancelled by the sender, awaiting the operator's confirmation of non-payment
+}
+
+// Where a remittance is paid out on completion
+#[derive(Clone)]
+#[contracttype]
+pub enum RemittanceKind {
+    Payout, // Paid out off-chain by a payout operator
+    FamilyPool(PoolDeposit),
+    Conditional(Condition),
+    Purchase(Purchase),
+}
+
+// Release condition of a conditional remittance (e.g. a school or hospital invoice)
+// The attester confirms the condition and the funds go to the payee; after expiry
+// the funds revert to the sender
+#[derive(Clone)]
+#[contracttype]
+pub struct Condition {
+    pub attester: Address,
+    pub condition_hash: BytesN<32>,
+    pub payee: Address,
+    pub expires_at: u64,
+}
+
+// Family pool a remittance is deposited into
+#[derive(Clone)]
+#[contracttype]
+pub struct PoolDeposit {
+    pub contract: Address,
+    pub pool_id: BytesN<32>,
+}
+
+// Goods purchase held in escrow for a merchant until the relative confirms delivery
+// Undelivered purchases are refunded after the delivery deadline
+#[derive(Clone)]
+#[contracttype]
+pub struct Purchase {
+    pub merchant: Address,
+    pub relative: Address,
+    pub delivery_deadline: u64,
+}
+
+// Purpose of a remittance, for regulatory reporting
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[contracttype]
+pub enum PurposeCode {
+    FamilySupport,
+    Education,
+    Medical,
+    Salary,
+    Gift,
+    Other,
 }
 
 // Simplified remittance data structure
 #[derive(Clone)]
 #[contracttype]
 pub struct Remittance {
+    pub id: BytesN<32>,
+    pub sender: Address,
+    pub recipient: BytesN<32>, // Salted hash of the normalised recipient identifier
+    pub amount: i128,
+    pub status: RemittanceStatus,
+    pub token: Address,
+    pub fee: i128,
+    pub corridor: String,
+    pub operator: Option<Address>,
+    pub assigned_at: u64,
+    pub accepted_at: u64,
+    pub escalations: u32,
+    pub created_at: u64,
+    pub completed_at: u64,
+    pub slashed: i128,
+    pub amendments: Vec<Amendment>,
+    pub travel_rule: Option<TravelRuleCommitment>,
+    pub travel_rule_acked_at: u64,
+    pub purpose: PurposeCode,
+    pub memo: String,
+    pub kind: RemittanceKind,
+    pub receipt_digest: Option<BytesN<32>>, // sha256 of the XDR-encoded receipt
+    pub cancel_requested_at: u64,
+    pub cancellation_fee: i128, // Retained into the treasury when the remittance is cancelled
+    pub refund_address: Address, // Where refunds are paid; the actual destination once refunded
+    pub pending_refund_address: Option<Address>,
+    pub refund_address_effective_at: u64,
+}
+
+// Canonical receipt of a completed remittance, for proof of payment
+// Its sha256 digest over the XDR encoding is stored on the remittance
+#[derive(Clone)]
+#[contracttype]
+pub struct Receipt {
+    pub remittance_id: BytesN<32>,
+    pub sender: Address,
+    pub recipient: BytesN<32>,
+    pub token: Address,
+    pub amount: i128,
+    pub fee: i128,
+    pub net_amount: i128, // Amount paid out after fees
+    pub rate: i128,       // Payout exchange rate in basis points (1:1 = 10000)
+    pub corridor: String,
+    pub purpose: PurposeCode,
+    pub operator: Option<Address>,
+    pub payee: Option<Address>, // On-chain beneficiary, if not paid out off-chain
+    pub payout_reference: String,
+    pub created_at: u64,
+    pub completed_at: u64,
+}
+
+// Commitment to the off-chain travel-rule payload (originator/beneficiary information)
+#[derive(Clone)]
+#[contracttype]
+pub struct TravelRuleCommitment {
+    pub payload_hash: BytesN<32>,
+    pub key_id: String, // Identifier of the key the payload is encrypted to
+}
+
+// Remittance layout of schema version 1, kept to migrate old records
+#[derive(Clone)]
+#[contracttype]
+pub struct LegacyRemittance {
     pub id: BytesN<32>,
     pub sender: Address,
     pub recipient: String,
@@ -22,6 +190,96 @@ pub struct Remittance {
     pub status: RemittanceStatus,
 }
 
+// Previous recipient details replaced by an amendment
+#[derive(Clone)]
+#[contracttype]
+pub struct Amendment {
+    pub previous_recipient: BytesN<32>,
+    pub amended_at: u64,
+}
+
+// Payout corridor with its operators and SLA windows (in seconds)
+#[derive(Clone)]
+#[contracttype]
+pub struct Corridor {
+    pub operators: Vec<Address>,
+    pub next_operator: u32,
+    pub accept_window: u64,
+    pub payout_sla: u64,
+    pub allowed_purposes: Vec<PurposeCode>, // Empty means every purpose is allowed
+}
+
+// Aggregate remittance statistics for a token and corridor over a period
+#[derive(Clone)]
+#[contracttype]
+pub struct VolumeStats {
+    pub count: u32,
+    pub volume: i128,
+    pub fees: i128,
+    pub refund_count: u32,
+    pub refunds: i128,
+}
+
+// Statistics bucket, by UTC calendar date
+#[derive(Clone)]
+#[contracttype]
+pub enum StatsPeriod {
+    Daily(u32),   // YYYYMMDD
+    Monthly(u32), // YYYYMM
+}
+
+// Claim voucher signed by the sender, releasing a remittance to an on-chain recipient
+#[derive(Clone)]
+#[contracttype]
+pub struct Voucher {
+    pub contract: Address,
+    pub remittance_id: BytesN<32>,
+    pub recipient: Address,
+    pub expiry: u64,
+    pub relayer_fee: i128,
+}
+
+// Completed remittance totals for a token and purpose
+#[derive(Clone)]
+#[contracttype]
+pub struct PurposeStats {
+    pub count: u32,
+    pub volume: i128,
+}
+
+// Per-operator SLA statistics
+#[derive(Clone)]
+#[contracttype]
+pub struct OperatorStats {
+    pub assigned: u32,
+    pub accepted: u32,
+    pub completed: u32,
+    pub completed_on_time: u32,
+    pub breaches: u32,
+    pub total_payout_time: u64,
+}
+
+// Part of an operator's exposure kept after a completed payout until its challenge period ends
+#[derive(Clone)]
+#[contracttype]
+pub struct BondLock {
+    pub remittance_id: BytesN<32>,
+    pub amount: i128,
+    pub unlocks_at: u64,
+}
+
+// Collateral posted by an operator in the bond token
+// In-flight exposure (assigned remittances, and completed ones still in their challenge period)
+// is capped at the bond amount. Bonded payouts are in the bond token, so both are in the same unit.
+#[derive(Clone)]
+#[contracttype]
+pub struct OperatorBond {
+    pub amount: i128,
+    pub exposure: i128,
+    pub slashed: i128,
+    pub locks: Vec<BondLock>,
+}
+
 // Contract storage keys
 #[derive(Clone)]
 #[contracttype]
@@ -31,6 +289,24 @@ pub enum DataKey {
     RemittanceCount,
     FeePercentage,
     UserRemittances(Address),
+    RecipientRemittances(BytesN<32>),
+    Corridor(String),
+    OperatorStats(Address),
+    BondToken,
+    Arbiter,
+    OperatorBond(Address),
+    TravelRuleThreshold(Address),
+    PurposeStats(Address, PurposeCode),
+    VoucherKey(Address),
+    Stats(Address, String, StatsPeriod),
+    SchemaVersion,
+    Receipt(BytesN<32>),
+    Treasury,
+    CancellationWindow,
+    CancellationFee,
+    ChallengePeriod,
+    LegacyCount,
+    MigratedCount,
 }
 
 #[contract]
@@ -54,14 +330,167 @@ impl RemittanceContract {
         env.storage().instance().set(&DataKey::Admin, &admin);
         env.storage().instance().set(&DataKey::FeePercentage, &fee_percentage);
         env.storage().instance().set(&DataKey::RemittanceCount, &0u32);
+        env.storage()
+            .instance()
+            .set(&DataKey::SchemaVersion, &CURRENT_SCHEMA_VERSION);
+    }
+
+    // Upgrade the contract code (admin only)
+    // Run migrate afterwards if the new code bumps the schema version
+    pub fn upgrade(env: Env, admin: Address, new_wasm_hash: BytesN<32>) {
+        // Verify admin
+        admin.require_auth();
+        require_admin(&env, &admin);
+
+        env.deployer().update_current_contract_wasm(new_wasm_hash);
+    }
+
+    // Convert a batch of schema version 1 remittances to the current layout (admin only)
+    // Old remittances held no escrow, so pending ones are converted as cancelled and
+    // refund nothing. Recipients are hashed with the remittance ID as salt. Records already
+    // in the current layout are skipped. Returns the number of converted records.
+    pub fn migrate(
+        env: Env,
+        admin: Address,
+        token: Address,
+        corridor: String,
+        ids: Vec<BytesN<32>>,
+    ) -> u32 {
+        // Verify admin
+        admin.require_auth();
+        require_admin(&env, &admin);
+
+        if schema_version(&env) >= CURRENT_SCHEMA_VERSION {
+            panic!("Storage already migrated");
+        }
+
+        if ids.len() > MAX_MIGRATION_BATCH {
+            panic!("Migration batch too large");
+        }
+
+        // Record how many old remittances there are before converting any
+        legacy_count(&env);
+
+        let mut migrated = 0u32;
+        for id in ids.iter() {
+            let raw = env
+                .storage()
+                .instance()
+                .get::<_, Map<Symbol, Val>>(&DataKey::Remittance(id.clone()))
+                .expect("Remittance not found");
+
+            // Skip records that are already in the current layout
+            if raw.contains_key(Symbol::new(&env, "token")) {
+                continue;
+            }
+
+            let legacy = LegacyRemittance::try_from_val(&env, &raw.to_val())
+                .expect("Unknown remittance layout");
+            // Identifiers the current rules reject are hashed as stored, so one bad
+            // record does not abort the batch
+            let mut recipient_bytes = try_normalize_identifier(&env, &legacy.recipient)
+                .unwrap_or_else(|| legacy.recipient.clone().to_xdr(&env));
+            recipient_bytes.append(&legacy.id.clone().into());
+            let recipient: BytesN<32> = env.crypto().sha256(&recipient_bytes).into();
+
+            let mut remittance = Remittance {
+                id: legacy.id.clone(),
+                sender: legacy.sender.clone(),
+                recipient: recipient.clone(),
+                amount: legacy.amount,
+                status: legacy.status,
+                token: token.clone(),
+                fee: 0,
+                corridor: corridor.clone(),
+                operator: None,
+                assigned_at: 0,
+                accepted_at: 0,
+                escalations: 0,
+                created_at: 0,
+                completed_at: 0,
+                slashed: 0,
+                amendments: vec![&env],
+                travel_rule: None,
+                travel_rule_acked_at: 0,
+                purpose: PurposeCode::Other,
+                memo: String::from_str(&env, ""),
+                kind: RemittanceKind::Payout,
+                receipt_digest: None,
+                cancel_requested_at: 0,
+                cancellation_fee: 0,
+                refund_address: legacy.sender.clone(),
+                pending_refund_address: None,
+                refund_address_effective_at: 0,
+            };
+
+            // Pending remittances were never funded, so they cannot be paid out or refunded
+            if remittance.status == RemittanceStatus::Pending {
+                remittance.status = RemittanceStatus::Cancelled;
+            }
+
+            env.storage()
+                .instance()
+                .set(&DataKey::Remittance(id.clone()), &remittance);
+            index_recipient(&env, &recipient, &id);
+            migrated += 1;
+        }
+
+        let total = env
+            .storage()
+            .instance()
+            .get::<_, u32>(&DataKey::MigratedCount)
+            .unwrap_or(0);
+        env.storage()
+            .instance()
+            .set(&DataKey::MigratedCount, &(total + migrated));
+
+        migrated
+    }
+
+    // Mark the storage migration as finished once every old record is converted (admin only)
+    // The old code overwrote remittances created in the same ledger while still counting them,
+    // so fewer records may exist than were counted. Once every remaining record is converted,
+    // the admin finishes with `acknowledge_missing` to accept the difference.
+    pub fn finish_migration(env: Env, admin: Address, acknowledge_missing: bool) {
+        // Verify admin
+        admin.require_auth();
+        require_admin(&env, &admin);
+
+        let migrated = env
+            .storage()
+            .instance()
+            .get::<_, u32>(&DataKey::MigratedCount)
+            .unwrap_or(0);
+        if schema_version(&env) < CURRENT_SCHEMA_VERSION
+            && migrated < legacy_count(&env)
+            && !acknowledge_missing
+        {
+            panic!("Remittances not yet migrated");
+        }
+
+        env.storage()
+            .instance()
+            .set(&DataKey::SchemaVersion, &CURRENT_SCHEMA_VERSION);
+    }
+
+    // Get the storage schema version
+    pub fn get_schema_version(env: Env) -> u32 {
+        schema_version(&env)
     }
 
     // Create a new remittance
     pub fn create_remittance(
         env: Env,
         sender: Address,
-        recipient: String,
+        recipient: BytesN<32>,
+        token: Address,
         amount: i128,
+        corridor: String,
+        travel_rule: Option<TravelRuleCommitment>,
+        purpose: PurposeCode,
+        memo: String,
+        kind: RemittanceKind,
+        refund_address: Option<Address>,
     ) -> BytesN<32> {
         // Verify sender
         sender.require_auth();
@@ -71,6 +500,56 @@ impl RemittanceContract {
             panic!("Amount must be positive");
         }
 
+        // Verify memo length
+        if memo.len() > MAX_MEMO_LEN {
+            panic!("Memo too long");
+        }
+
+        // Verify the corridor is configured and allows the purpose
+        let corridor_config = env
+            .storage()
+            .instance()
+            .get::<_, Corridor>(&DataKey::Corridor(corridor.clone()))
+            .expect("Corridor not found");
+        if !corridor_config.allowed_purposes.is_empty()
+            && !corridor_config.allowed_purposes.contains(&purpose)
+        {
+            panic!("Purpose not allowed in corridor");
+        }
+
+        // Conditional remittances and purchases need an expiry in the future
+        match &kind {
+            RemittanceKind::Conditional(condition) => {
+                if condition.expires_at <= env.ledger().timestamp() {
+                    panic!("Condition expiry must be in the future");
+                }
+            }
+            RemittanceKind::Purchase(purchase) => {
+                if purchase.delivery_deadline <= env.ledger().timestamp() {
+                    panic!("Delivery deadline must be in the future");
+                }
+            }
+            // The pool must accept the sender's deposit in this token, or completion could never succeed
+            RemittanceKind::FamilyPool(deposit) => {
+                let pool_client = FamilyPoolClient::new(&env, &deposit.contract);
+                if pool_client.get_deposit_token(&deposit.pool_id, &sender) != token {
+                    panic!("Pool token does not match remittance token");
+                }
+            }
+            _ => {}
+        }
+
+        // Transfers at or above the token's travel-rule threshold need a payload commitment
+        let threshold = env
+            .storage()
+            .instance()
+            .get::<_, i128>(&DataKey::TravelRuleThreshold(token.clone()));
+        if let Some(threshold) = threshold {
+            if amount >= threshold && travel_rule.is_none() {
+                panic!("Travel rule commitment required");
+            }
+        }
+
         // Get fee percentage
         let fee_percentage = env
             .storage()
@@ -90,13 +569,23 @@ impl RemittanceContract {
             
         // Generate a unique ID using the count and timestamp
         let timestamp = env.ledger().timestamp();
-        let bytes = Bytes::from_slice(&env, &timestamp.to_be_bytes());
+        let mut bytes = Bytes::from_slice(&env, &timestamp.to_be_bytes());
+        bytes.extend_from_slice(&count.to_be_bytes());
         let id = env.crypto().sha256(&bytes);
         
         
+        // Record how many old remittances there are before counting a new one
+        if schema_version(&env) < CURRENT_SCHEMA_VERSION {
+            legacy_count(&env);
+        }
+
         // Increment the remittance count
         env.storage().instance().set(&DataKey::RemittanceCount, &(count + 1));
 
+        // Hold the funds in escrow until the payout is completed
+        let token_client = token::Client::new(&env, &token);
+        token_client.transfer(&sender, &env.current_contract_address(), &amount);
+
         // Create remittance record
         let remittance = Remittance {
             id: id.clone(),
@@ -104,6 +593,28 @@ impl RemittanceContract {
             recipient: recipient.clone(),
             amount: amount,
             status: RemittanceStatus::Pending,
+            token,
+            fee,
+            corridor,
+            operator: None,
+            assigned_at: 0,
+            accepted_at: 0,
+            escalations: 0,
+            created_at: timestamp,
+            completed_at: 0,
+            slashed: 0,
+            amendments: vec![&env],
+            travel_rule,
+            travel_rule_acked_at: 0,
+            purpose,
+            memo,
+            kind,
+            receipt_digest: None,
+            cancel_requested_at: 0,
+            cancellation_fee: 0,
+            refund_address: refund_address.unwrap_or(sender.clone()),
+            pending_refund_address: None,
+            refund_address_effective_at: 0,
         };
 
         // Store remittance data
@@ -122,31 +633,28 @@ impl RemittanceContract {
             .instance()
             .set(&DataKey::UserRemittances(sender), &user_remittances);
 
+        // Index the remittance by recipient hash
+        index_recipient(&env, &recipient, &id);
 
+        // Update volume statistics
+        record_stats(&env, &remittance, |stats| {
+            stats.count += 1;
+            stats.volume += remittance.amount;
+        });
 
         // Return the remittance ID
         id
     }
 
-    // Complete a remittance
-    pub fn complete_remittance(
+    // Amend the recipient of a pending remittance that has not been assigned yet
+    pub fn amend_remittance(
         env: Env,
+        sender: Address,
         id: BytesN<32>,
-        admin: Address,
+        new_recipient: BytesN<32>,
     ) -> bool {
-        // Verify admin authorization
-        admin.require_auth();
-        
-        // Get stored admin
-        let stored_admin = env
-            .storage()
-            .instance()
-            .get::<_, Address>(&DataKey::Admin)
-            .expect("Admin not set");
-            
-        if admin != stored_admin {
-            panic!("Only admin can complete remittances");
-        }
+        // Verify sender
+        sender.require_auth();
 
         // Get remittance data
         let mut remittance = env
@@ -155,13 +663,31 @@ impl RemittanceContract {
             .get::<_, Remittance>(&DataKey::Remittance(id.clone()))
             .expect("Remittance not found");
 
-        // Check if remittance is pending
+        // Check if sender is the original sender
+        if remittance.sender != sender {
+            panic!("Only the sender can amend the remittance");
+        }
+
+        // Check if remittance is pending and unassigned
         if remittance.status != RemittanceStatus::Pending {
-            panic!("Remittance is not pending");
+            panic!("Only pending remittances can be amended");
+        }
+        if remittance.operator.is_some() {
+            panic!("Remittance already assigned to an operator");
         }
 
-        // Update remittance status
-        remittance.status = RemittanceStatus::Completed;
+        if remittance.amendments.len() >= MAX_AMENDMENTS {
+            panic!("Amendment limit reached");
+        }
+
+        // Keep the replaced recipient in the amendment history
+        remittance.amendments.push_back(Amendment {
+            previous_recipient: remittance.recipient.clone(),
+            amended_at: env.ledger().timestamp(),
+        });
+        unindex_recipient(&env, &remittance.recipient, &id);
+        index_recipient(&env, &new_recipient, &id);
+        remittance.recipient = new_recipient;
 
         // Store updated remittance
         env.storage()
@@ -171,8 +697,14 @@ impl RemittanceContract {
         true
     }
 
-    // Cancel a remittance
-    pub fn cancel_remittance(env: Env, sender: Address, id: BytesN<32>) -> bool {
+    // Change where a remittance is refunded to (sender only)
+    // The new address takes effect after a delay, so a stolen key cannot redirect refunds at once
+    pub fn change_refund_address(
+        env: Env,
+        sender: Address,
+        id: BytesN<32>,
+        new_refund_address: Address,
+    ) -> u64 {
         // Verify sender
         sender.require_auth();
 
@@ -185,59 +717,1729 @@ impl RemittanceContract {
 
         // Check if sender is the original sender
         if remittance.sender != sender {
-            panic!("Only the sender can cancel the remittance");
+            panic!("Only the sender can change the refund address");
         }
 
-        // Check if remittance is pending
-        if remittance.status != RemittanceStatus::Pending {
-            panic!("Only pending remittances can be cancelled");
+        if remittance.status != RemittanceStatus::Pending
+            && remittance.status != RemittanceStatus::Delivered
+            && remittance.status != RemittanceStatus::Disputed
+        {
+            panic!("Remittance can no longer be refunded");
         }
 
-        // Update remittance status
-        remittance.status = RemittanceStatus::Cancelled;
+        // Replace any earlier pending change and restart the delay
+        remittance.pending_refund_address = Some(new_refund_address);
+        remittance.refund_address_effective_at = env.ledger().timestamp() + REFUND_ADDRESS_DELAY;
 
-        // Store updated remittance
         env.storage()
             .instance()
             .set(&DataKey::Remittance(id), &remittance);
 
-        true
+        remittance.refund_address_effective_at
     }
 
-    // Get remittance details
-    pub fn get_remittance(env: Env, id: BytesN<32>) -> Remittance {
+    // Assign a remittance to a payout operator (admin only)
+    // Passing no operator picks the next operator of the corridor in round-robin order
+    pub fn assign_operator(
+        env: Env,
+        admin: Address,
+        id: BytesN<32>,
+        operator: Option<Address>,
+    ) -> Address {
+        // Verify admin
+        admin.require_auth();
+        require_admin(&env, &admin);
+
+        // Get remittance data
+        let mut remittance = env
+            .storage()
+            .instance()
+            .get::<_, Remittance>(&DataKey::Remittance(id.clone()))
+            .expect("Remittance not found");
+
+        // Check if remittance is pending
+        if remittance.status != RemittanceStatus::Pending {
+            panic!("Remittance is not pending");
+        }
+
+        // Only off-chain payouts are handled by operators
+        if !matches!(remittance.kind, RemittanceKind::Payout) {
+            panic!("Remittance is not an off-chain payout");
+        }
+
+        // Only unaccepted remittances can be reassigned
+        if remittance.accepted_at != 0 {
+            panic!("Payout already accepted");
+        }
+
+        let mut corridor = env
+            .storage()
+            .instance()
+            .get::<_, Corridor>(&DataKey::Corridor(remittance.corridor.clone()))
+            .expect("Corridor not found");
+
+        let operator = match operator {
+            Some(operator) => {
+                if !corridor.operators.contains(&operator) {
+                    panic!("Operator not registered for corridor");
+                }
+                operator
+            }
+            None => next_operator(&env, &mut corridor, None, &remittance)
+                .expect("No operator available"),
+        };
+
         env.storage()
             .instance()
-            .get::<_, Remittance>(&DataKey::Remittance(id))
-            .expect("Remittance not found")
-    }
+            .set(&DataKey::Corridor(remittance.corridor.clone()), &corridor);
 
+        assign(&env, &mut remittance, operator.clone());
+        env.storage()
+            .instance()
+            .set(&DataKey::Remittance(id), &remittance);
 
+        operator
+    }
 
-    // Update fee percentage (admin only)
-    pub fn update_fee_percentage(env: Env, admin: Address, fee_percentage: i128) {
-        // Verify admin
-        admin.require_auth();
+    // Accept a payout, starting the payout SLA timer
+    pub fn accept_payout(env: Env, operator: Address, id: BytesN<32>) -> bool {
+        // Verify operator
+        operator.require_auth();
 
-        // Check if caller is admin
-        let stored_admin = env
+        // Get remittance data
+        let mut remittance = env
             .storage()
             .instance()
-            .get::<_, Address>(&DataKey::Admin)
-            .expect("Contract not initialized");
+            .get::<_, Remittance>(&DataKey::Remittance(id.clone()))
+            .expect("Remittance not found");
 
-        if stored_admin != admin {
-            panic!("Only admin can update fee percentage");
+        // Check if remittance is pending
+        if remittance.status != RemittanceStatus::Pending {
+            panic!("Remittance is not pending");
         }
 
-        // Verify fee percentage is reasonable
-        if fee_percentage > 1000 {
-            panic!("Fee percentage must be less than 10% (1000 basis points)");
+        // Check if caller is the assigned operator
+        if remittance.operator != Some(operator.clone()) {
+            panic!("Only the assigned operator can accept the payout");
         }
 
-        // Update fee percentage
-        env.storage().instance().set(&DataKey::FeePercentage, &fee_percentage);
+        if remittance.accepted_at != 0 {
+            panic!("Payout already accepted");
+        }
+
+        // Start the SLA timer
+        remittance.accepted_at = env.ledger().timestamp();
+        env.storage()
+            .instance()
+            .set(&DataKey::Remittance(id), &remittance);
+
+        let mut stats = read_operator_stats(&env, &operator);
+        stats.accepted += 1;
+        env.storage()
+            .instance()
+            .set(&DataKey::OperatorStats(operator), &stats);
+
+        true
+    }
+
+    // Acknowledge receipt of the travel-rule payload (assigned operator only)
+    pub fn acknowledge_travel_rule(env: Env, operator: Address, id: BytesN<32>) -> bool {
+        // Verify operator
+        operator.require_auth();
+
+        // Get remittance data
+        let mut remittance = env
+            .storage()
+            .instance()
+            .get::<_, Remittance>(&DataKey::Remittance(id.clone()))
+            .expect("Remittance not found");
+
+        // Check if remittance is pending
+        if remittance.status != RemittanceStatus::Pending {
+            panic!("Remittance is not pending");
+        }
+
+        // Check if caller is the assigned operator
+        if remittance.operator != Some(operator) {
+            panic!("Only the assigned operator can acknowledge the travel rule payload");
+        }
+
+        if remittance.travel_rule.is_none() {
+            panic!("Remittance has no travel rule commitment");
+        }
+
+        remittance.travel_rule_acked_at = env.ledger().timestamp();
+        env.storage()
+            .instance()
+            .set(&DataKey::Remittance(id), &remittance);
+
+        true
+    }
+
+    // Escalate a remittance whose operator missed its SLA
+    // Anyone can call this; the remittance is reassigned or refunded to the sender
+    pub fn escalate(env: Env, id: BytesN<32>) -> RemittanceStatus {
+        // Get remittance data
+        let mut remittance = env
+            .storage()
+            .instance()
+            .get::<_, Remittance>(&DataKey::Remittance(id.clone()))
+            .expect("Remittance not found");
+
+        let operator = remittance.operator.clone().expect("Remittance is not assigned");
+
+        let mut corridor = env
+            .storage()
+            .instance()
+            .get::<_, Corridor>(&DataKey::Corridor(remittance.corridor.clone()))
+            .expect("Corridor not found");
+
+        // An operator that does not answer a cancellation request within the
+        // acceptance window is taken not to have paid out
+        if remittance.status == RemittanceStatus::CancelRequested {
+            if env.ledger().timestamp() <= remittance.cancel_requested_at + corridor.accept_window {
+                panic!("SLA has not lapsed");
+            }
+
+            let mut stats = read_operator_stats(&env, &operator);
+            stats.breaches += 1;
+            env.storage()
+                .instance()
+                .set(&DataKey::OperatorStats(operator), &stats);
+
+            finish_cancellation(&env, &mut remittance);
+            env.storage()
+                .instance()
+                .set(&DataKey::Remittance(id), &remittance);
+
+            return remittance.status;
+        }
+
+        // Check if remittance is pending
+        if remittance.status != RemittanceStatus::Pending {
+            panic!("Remittance is not pending");
+        }
+
+        // Check if the SLA has lapsed
+        let deadline = if remittance.accepted_at == 0 {
+            remittance.assigned_at + corridor.accept_window
+        } else {
+            remittance.accepted_at + corridor.payout_sla
+        };
+        if env.ledger().timestamp() <= deadline {
+            panic!("SLA has not lapsed");
+        }
+
+        // Record the breach against the operator
+        let mut stats = read_operator_stats(&env, &operator);
+        stats.breaches += 1;
+        env.storage()
+            .instance()
+            .set(&DataKey::OperatorStats(operator.clone()), &stats);
+
+        remittance.escalations += 1;
+        remittance.accepted_at = 0;
+
+        // Reassign to another operator, or refund once escalations are exhausted
+        let replacement = if remittance.escalations < MAX_ESCALATIONS {
+            next_operator(&env, &mut corridor, Some(&operator), &remittance)
+        } else {
+            None
+        };
+
+        match replacement {
+            Some(replacement) => {
+                env.storage()
+                    .instance()
+                    .set(&DataKey::Corridor(remittance.corridor.clone()), &corridor);
+                assign(&env, &mut remittance, replacement);
+            }
+            None => {
+                release_exposure(&env, &operator, remittance.amount);
+                refund(&env, &mut remittance);
+
+                remittance.operator = None;
+                remittance.status = RemittanceStatus::Refunded;
+                remittance.completed_at = env.ledger().timestamp();
+            }
+        }
+
+        env.storage()
+            .instance()
+            .set(&DataKey::Remittance(id), &remittance);
+
+        remittance.status
+    }
+
+    // Complete a remittance (admin or the assigned operator)
+    // The payout rate and reference (e.g. the mobile money transaction code) go on the receipt
+    pub fn complete_remittance(
+        env: Env,
+        id: BytesN<32>,
+        caller: Address,
+        rate: i128,
+        payout_reference: String,
+    ) -> bool {
+        // Verify caller authorization
+        caller.require_auth();
+        
+        // Get stored admin
+        let stored_admin = env
+            .storage()
+            .instance()
+            .get::<_, Address>(&DataKey::Admin)
+            .expect("Admin not set");
+
+        // Get remittance data
+        let mut remittance = env
+            .storage()
+            .instance()
+            .get::<_, Remittance>(&DataKey::Remittance(id.clone()))
+            .expect("Remittance not found");
+
+        let is_operator = remittance.operator == Some(caller.clone());
+        if caller != stored_admin && !is_operator {
+            panic!("Only admin or the assigned operator can complete remittances");
+        }
+
+        // Check if remittance is pending; an operator that already paid out
+        // completes the remittance instead of confirming a cancellation request
+        if remittance.status != RemittanceStatus::Pending
+            && remittance.status != RemittanceStatus::CancelRequested
+        {
+            panic!("Remittance is not pending");
+        }
+        remittance.cancellation_fee = 0;
+
+        // Verify rate is positive
+        if rate <= 0 {
+            panic!("Rate must be positive");
+        }
+
+        // Operators must accept the payout before completing it
+        if is_operator && remittance.accepted_at == 0 {
+            panic!("Payout not accepted");
+        }
+
+        match remittance.kind {
+            RemittanceKind::Conditional(_) => {
+                panic!("Conditional remittances are released by their attester")
+            }
+            RemittanceKind::Purchase(_) => {
+                panic!("Purchases are released by delivery confirmation")
+            }
+            _ => {}
+        }
+
+        // The receiving operator must have acknowledged the travel-rule payload
+        // (pool deposits stay on-ledger and have no receiving operator)
+        let is_payout = matches!(remittance.kind, RemittanceKind::Payout);
+        if is_payout && remittance.travel_rule.is_some() && remittance.travel_rule_acked_at == 0 {
+            panic!("Travel rule payload not acknowledged");
+        }
+
+        // Update remittance status
+        remittance.status = RemittanceStatus::Completed;
+        remittance.completed_at = env.ledger().timestamp();
+
+        // Release the escrow to the operator who paid out or into the family pool,
+        // and the fee to the treasury
+        let token_client = token::Client::new(&env, &remittance.token);
+        let payee = match remittance.kind.clone() {
+            RemittanceKind::Payout => {
+                let payee = remittance.operator.clone().unwrap_or(stored_admin.clone());
+                token_client.transfer(
+                    &env.current_contract_address(),
+                    &payee,
+                    &(remittance.amount - remittance.fee),
+                );
+                None
+            }
+            RemittanceKind::FamilyPool(deposit) => {
+                deposit_to_pool(&env, &remittance, &deposit);
+                Some(deposit.contract)
+            }
+            RemittanceKind::Conditional(_) | RemittanceKind::Purchase(_) => unreachable!(),
+        };
+        if remittance.fee > 0 {
+            token_client.transfer(&env.current_contract_address(), &treasury(&env), &remittance.fee);
+        }
+
+        // Keep the operator's exposure locked through the challenge period and update SLA statistics
+        if let Some(operator) = remittance.operator.clone() {
+            lock_exposure(&env, &operator, &remittance);
+
+            let corridor = env
+                .storage()
+                .instance()
+                .get::<_, Corridor>(&DataKey::Corridor(remittance.corridor.clone()))
+                .expect("Corridor not found");
+
+            let mut stats = read_operator_stats(&env, &operator);
+            stats.completed += 1;
+            if remittance.accepted_at != 0 {
+                let payout_time = remittance.completed_at - remittance.accepted_at;
+                stats.total_payout_time += payout_time;
+                if payout_time <= corridor.payout_sla {
+                    stats.completed_on_time += 1;
+                }
+            }
+            env.storage()
+                .instance()
+                .set(&DataKey::OperatorStats(operator), &stats);
+        }
+
+        // Update reporting aggregates
+        record_completion(&env, &remittance);
+
+        // Issue the receipt
+        let net_amount = remittance.amount - remittance.fee;
+        issue_receipt(&env, &mut remittance, net_amount, rate, payee, payout_reference);
+
+        // Store updated remittance
+        env.storage()
+            .instance()
+            .set(&DataKey::Remittance(id), &remittance);
+
+        true
+    }
+
+    // Confirm the condition of a conditional remittance, releasing the funds to the payee
+    pub fn attest(
+        env: Env,
+        attester: Address,
+        id: BytesN<32>,
+        condition_hash: BytesN<32>,
+    ) -> bool {
+        // Verify attester
+        attester.require_auth();
+
+        // Get remittance data
+        let mut remittance = env
+            .storage()
+            .instance()
+            .get::<_, Remittance>(&DataKey::Remittance(id.clone()))
+            .expect("Remittance not found");
+
+        // Check if remittance is pending
+        if remittance.status != RemittanceStatus::Pending {
+            panic!("Remittance is not pending");
+        }
+
+        let condition = match remittance.kind.clone() {
+            RemittanceKind::Conditional(condition) => condition,
+            _ => panic!("Remittance is not conditional"),
+        };
+
+        if condition.attester != attester {
+            panic!("Only the attester can confirm the condition");
+        }
+        if condition.condition_hash != condition_hash {
+            panic!("Condition does not match");
+        }
+        if env.ledger().timestamp() > condition.expires_at {
+            panic!("Condition expired");
+        }
+
+        // Release the escrow to the payee
+        release_to(&env, &mut remittance, &condition.payee);
+
+        // Store updated remittance
+        env.storage()
+            .instance()
+            .set(&DataKey::Remittance(id), &remittance);
+
+        true
+    }
+
+    // Refund a conditional remittance whose condition expired unconfirmed, or a
+    // purchase that was not delivered by its deadline
+    // Anyone can call this; the funds revert to the sender
+    pub fn refund_expired(env: Env, id: BytesN<32>) -> bool {
+        // Get remittance data
+        let mut remittance = env
+            .storage()
+            .instance()
+            .get::<_, Remittance>(&DataKey::Remittance(id.clone()))
+            .expect("Remittance not found");
+
+        // Check if remittance is pending
+        if remittance.status != RemittanceStatus::Pending {
+            panic!("Remittance is not pending");
+        }
+
+        let expires_at = match &remittance.kind {
+            RemittanceKind::Conditional(condition) => condition.expires_at,
+            RemittanceKind::Purchase(purchase) => purchase.delivery_deadline,
+            _ => panic!("Remittance does not expire"),
+        };
+        if env.ledger().timestamp() <= expires_at {
+            panic!("Remittance has not expired");
+        }
+
+        // Update remittance status
+        remittance.status = RemittanceStatus::Refunded;
+        remittance.completed_at = env.ledger().timestamp();
+
+        // Refund the escrowed amount
+        refund(&env, &mut remittance);
+
+        // Store updated remittance
+        env.storage()
+            .instance()
+            .set(&DataKey::Remittance(id), &remittance);
+
+        true
+    }
+
+    // Mark a purchase as delivered (merchant only)
+    pub fn mark_delivered(env: Env, merchant: Address, id: BytesN<32>) -> bool {
+        // Verify merchant
+        merchant.require_auth();
+
+        // Get remittance data
+        let mut remittance = env
+            .storage()
+            .instance()
+            .get::<_, Remittance>(&DataKey::Remittance(id.clone()))
+            .expect("Remittance not found");
+
+        let purchase = get_purchase(&remittance);
+        if purchase.merchant != merchant {
+            panic!("Only the merchant can mark the purchase delivered");
+        }
+
+        // Check if remittance is pending
+        if remittance.status != RemittanceStatus::Pending {
+            panic!("Remittance is not pending");
+        }
+
+        // Past the deadline the purchase can only be refunded
+        if env.ledger().timestamp() > purchase.delivery_deadline {
+            panic!("Delivery deadline passed");
+        }
+
+        // Update remittance status
+        remittance.status = RemittanceStatus::Delivered;
+        env.storage()
+            .instance()
+            .set(&DataKey::Remittance(id), &remittance);
+
+        true
+    }
+
+    // Confirm delivery of a purchase, releasing the escrow to the merchant (relative only)
+    pub fn confirm_delivery(env: Env, relative: Address, id: BytesN<32>) -> bool {
+        // Verify relative
+        relative.require_auth();
+
+        // Get remittance data
+        let mut remittance = env
+            .storage()
+            .instance()
+            .get::<_, Remittance>(&DataKey::Remittance(id.clone()))
+            .expect("Remittance not found");
+
+        let purchase = get_purchase(&remittance);
+        if purchase.relative != relative {
+            panic!("Only the relative can confirm delivery");
+        }
+
+        if remittance.status != RemittanceStatus::Pending
+            && remittance.status != RemittanceStatus::Delivered
+        {
+            panic!("Purchase is not awaiting delivery");
+        }
+
+        // Release the escrow to the merchant
+        release_to(&env, &mut remittance, &purchase.merchant);
+
+        // Store updated remittance
+        env.storage()
+            .instance()
+            .set(&DataKey::Remittance(id), &remittance);
+
+        true
+    }
+
+    // Dispute a purchase (merchant or relative), freezing it until the admin or arbiter resolves it
+    pub fn dispute_purchase(env: Env, caller: Address, id: BytesN<32>) -> bool {
+        // Verify caller
+        caller.require_auth();
+
+        // Get remittance data
+        let mut remittance = env
+            .storage()
+            .instance()
+            .get::<_, Remittance>(&DataKey::Remittance(id.clone()))
+            .expect("Remittance not found");
+
+        let purchase = get_purchase(&remittance);
+        if caller != purchase.merchant && caller != purchase.relative {
+            panic!("Only the merchant or relative can dispute the purchase");
+        }
+
+        if remittance.status != RemittanceStatus::Pending
+            && remittance.status != RemittanceStatus::Delivered
+        {
+            panic!("Purchase is not awaiting delivery");
+        }
+
+        // Update remittance status
+        remittance.status = RemittanceStatus::Disputed;
+        env.storage()
+            .instance()
+            .set(&DataKey::Remittance(id), &remittance);
+
+        true
+    }
+
+    // Resolve a disputed purchase by paying the merchant or refunding the sender (admin or arbiter)
+    pub fn resolve_dispute(
+        env: Env,
+        caller: Address,
+        id: BytesN<32>,
+        release_to_merchant: bool,
+    ) -> RemittanceStatus {
+        // Verify caller
+        caller.require_auth();
+
+        let stored_admin = env
+            .storage()
+            .instance()
+            .get::<_, Address>(&DataKey::Admin)
+            .expect("Contract not initialized");
+        let arbiter = env.storage().instance().get::<_, Address>(&DataKey::Arbiter);
+
+        if caller != stored_admin && Some(caller) != arbiter {
+            panic!("Only admin or arbiter can resolve disputes");
+        }
+
+        // Get remittance data
+        let mut remittance = env
+            .storage()
+            .instance()
+            .get::<_, Remittance>(&DataKey::Remittance(id.clone()))
+            .expect("Remittance not found");
+
+        let purchase = get_purchase(&remittance);
+        if remittance.status != RemittanceStatus::Disputed {
+            panic!("Purchase is not disputed");
+        }
+
+        if release_to_merchant {
+            release_to(&env, &mut remittance, &purchase.merchant);
+        } else {
+            remittance.status = RemittanceStatus::Refunded;
+            remittance.completed_at = env.ledger().timestamp();
+            refund(&env, &mut remittance);
+        }
+
+        // Store updated remittance
+        env.storage()
+            .instance()
+            .set(&DataKey::Remittance(id), &remittance);
+
+        remittance.status
+    }
+
+    // Register the ed25519 public key the sender signs claim vouchers with
+    pub fn set_voucher_key(env: Env, sender: Address, public_key: BytesN<32>) {
+        // Verify sender
+        sender.require_auth();
+
+        env.storage()
+            .instance()
+            .set(&DataKey::VoucherKey(sender), &public_key);
+    }
+
+    // Redeem a sender-signed claim voucher on behalf of the recipient
+    // Any relayer can submit the voucher; the escrow is released to the named
+    // recipient and the relayer receives the fee the sender signed for
+    pub fn redeem_voucher(
+        env: Env,
+        relayer: Address,
+        voucher: Voucher,
+        signature: BytesN<64>,
+    ) -> bool {
+        // Verify relayer
+        relayer.require_auth();
+
+        // Check the voucher is for this contract and has not expired
+        if voucher.contract != env.current_contract_address() {
+            panic!("Voucher is for another contract");
+        }
+        if env.ledger().timestamp() > voucher.expiry {
+            panic!("Voucher expired");
+        }
+
+        // Get remittance data
+        let mut remittance = env
+            .storage()
+            .instance()
+            .get::<_, Remittance>(&DataKey::Remittance(voucher.remittance_id.clone()))
+            .expect("Remittance not found");
+
+        // Check if remittance is pending
+        if remittance.status != RemittanceStatus::Pending {
+            panic!("Remittance is not pending");
+        }
+
+        // Only off-chain payouts can be claimed with a voucher
+        if !matches!(remittance.kind, RemittanceKind::Payout) {
+            panic!("Remittance is not an off-chain payout");
+        }
+
+        // An operator that accepted the payout may already have paid out cash
+        if remittance.accepted_at != 0 {
+            panic!("Payout already accepted");
+        }
+
+        if remittance.travel_rule.is_some() && remittance.travel_rule_acked_at == 0 {
+            panic!("Travel rule payload not acknowledged");
+        }
+
+        // Verify the sender's signature over the voucher
+        let public_key = env
+            .storage()
+            .instance()
+            .get::<_, BytesN<32>>(&DataKey::VoucherKey(remittance.sender.clone()))
+            .expect("Voucher key not set");
+        env.crypto()
+            .ed25519_verify(&public_key, &voucher.clone().to_xdr(&env), &signature);
+
+        // Verify relayer fee is within the cap
+        if voucher.relayer_fee < 0
+            || voucher.relayer_fee > (remittance.amount * MAX_RELAYER_FEE_BPS) / 10000
+        {
+            panic!("Relayer fee exceeds cap");
+        }
+
+        // Free the assigned operator's bond capacity; the operator made no payout,
+        // so it is neither slashable nor named on the receipt
+        if let Some(operator) = remittance.operator.clone() {
+            release_exposure(&env, &operator, remittance.amount);
+        }
+        remittance.operator = None;
+
+        // Update remittance status
+        remittance.status = RemittanceStatus::Completed;
+        remittance.completed_at = env.ledger().timestamp();
+
+        // Release the escrow to the recipient, the relayer and the treasury
+        let token_client = token::Client::new(&env, &remittance.token);
+        token_client.transfer(
+            &env.current_contract_address(),
+            &voucher.recipient,
+            &(remittance.amount - remittance.fee - voucher.relayer_fee),
+        );
+        if voucher.relayer_fee > 0 {
+            token_client.transfer(&env.current_contract_address(), &relayer, &voucher.relayer_fee);
+        }
+        if remittance.fee > 0 {
+            token_client.transfer(&env.current_contract_address(), &treasury(&env), &remittance.fee);
+        }
+
+        // Update reporting aggregates
+        record_completion(&env, &remittance);
+
+        // Issue the receipt
+        let net_amount = remittance.amount - remittance.fee - voucher.relayer_fee;
+        issue_receipt(
+            &env,
+            &mut remittance,
+            net_amount,
+            10000,
+            Some(voucher.recipient.clone()),
+            String::from_str(&env, "voucher"),
+        );
+
+        // Store updated remittance
+        env.storage()
+            .instance()
+            .set(&DataKey::Remittance(voucher.remittance_id), &remittance);
+
+        true
+    }
+
+    // Cancel a remittance
+    pub fn cancel_remittance(env: Env, sender: Address, id: BytesN<32>) -> bool {
+        // Verify sender
+        sender.require_auth();
+
+        // Get remittance data
+        let mut remittance = env
+            .storage()
+            .instance()
+            .get::<_, Remittance>(&DataKey::Remittance(id.clone()))
+            .expect("Remittance not found");
+
+        // Check if sender is the original sender
+        if remittance.sender != sender {
+            panic!("Only the sender can cancel the remittance");
+        }
+
+        // Check if remittance is pending
+        if remittance.status != RemittanceStatus::Pending {
+            panic!("Only pending remittances can be cancelled");
+        }
+
+        // Conditional remittances revert to the sender only on expiry, so the sender
+        // cannot front-run the attestation
+        if matches!(remittance.kind, RemittanceKind::Conditional(_)) {
+            panic!("Conditional remittances are refunded on expiry");
+        }
+
+        // Purchases are refunded only past the delivery deadline or by dispute resolution,
+        // so the sender cannot take back the escrow after the goods are handed over
+        if matches!(remittance.kind, RemittanceKind::Purchase(_)) {
+            panic!("Purchases are refunded on expiry or by dispute resolution");
+        }
+
+        // Cancelling after the cooling-off window costs the cancellation fee
+        let window = env
+            .storage()
+            .instance()
+            .get::<_, u64>(&DataKey::CancellationWindow)
+            .unwrap_or(0);
+        if env.ledger().timestamp() > remittance.created_at + window {
+            let fee_percentage = env
+                .storage()
+                .instance()
+                .get::<_, i128>(&DataKey::CancellationFee)
+                .unwrap_or(0);
+            remittance.cancellation_fee = (remittance.amount * fee_percentage) / 10000;
+        }
+
+        if remittance.operator.is_some() {
+            // The assigned operator may already have paid out, so it has to confirm first
+            remittance.status = RemittanceStatus::CancelRequested;
+            remittance.cancel_requested_at = env.ledger().timestamp();
+        } else {
+            finish_cancellation(&env, &mut remittance);
+        }
+
+        // Store updated remittance
+        env.storage()
+            .instance()
+            .set(&DataKey::Remittance(id), &remittance);
+
+        true
+    }
+
+    // Confirm that a remittance with a cancellation request was not paid out,
+    // cancelling it and refunding the sender (assigned operator only)
+    pub fn confirm_cancellation(env: Env, operator: Address, id: BytesN<32>) -> bool {
+        // Verify operator
+        operator.require_auth();
+
+        // Get remittance data
+        let mut remittance = env
+            .storage()
+            .instance()
+            .get::<_, Remittance>(&DataKey::Remittance(id.clone()))
+            .expect("Remittance not found");
+
+        if remittance.status != RemittanceStatus::CancelRequested {
+            panic!("Cancellation not requested");
+        }
+
+        // Check if caller is the assigned operator
+        if remittance.operator != Some(operator) {
+            panic!("Only the assigned operator can confirm the cancellation");
+        }
+
+        finish_cancellation(&env, &mut remittance);
+
+        // Store updated remittance
+        env.storage()
+            .instance()
+            .set(&DataKey::Remittance(id), &remittance);
+
+        true
+    }
+
+    // Get remittance details
+    pub fn get_remittance(env: Env, id: BytesN<32>) -> Remittance {
+        env.storage()
+            .instance()
+            .get::<_, Remittance>(&DataKey::Remittance(id))
+            .expect("Remittance not found")
+    }
+
+
+
+    // Get the receipt of a completed remittance
+    pub fn get_receipt(env: Env, id: BytesN<32>) -> Receipt {
+        env.storage()
+            .persistent()
+            .get::<_, Receipt>(&DataKey::Receipt(id))
+            .expect("Receipt not found")
+    }
+
+    // Get the remittances sent to a recipient hash
+    pub fn get_recipient_remittances(env: Env, recipient: BytesN<32>) -> Vec<BytesN<32>> {
+        env.storage()
+            .instance()
+            .get::<_, Vec<BytesN<32>>>(&DataKey::RecipientRemittances(recipient))
+            .unwrap_or(vec![&env])
+    }
+
+    // Hash a recipient identifier the way it is stored on a remittance
+    // The identifier is normalised by dropping separators and upper-casing letters
+    pub fn hash_recipient(env: Env, identifier: String, salt: BytesN<32>) -> BytesN<32> {
+        let mut bytes = normalize_identifier(&env, &identifier);
+        bytes.append(&salt.into());
+        env.crypto().sha256(&bytes)
+    }
+
+    // Check a recipient's identifier against a remittance at payout
+    // Operators should simulate this call rather than submit it, so the
+    // identifier and salt are never published on-chain
+    pub fn verify_recipient(
+        env: Env,
+        id: BytesN<32>,
+        identifier: String,
+        salt: BytesN<32>,
+    ) -> bool {
+        let remittance = env
+            .storage()
+            .instance()
+            .get::<_, Remittance>(&DataKey::Remittance(id))
+            .expect("Remittance not found");
+
+        Self::hash_recipient(env, identifier, salt) == remittance.recipient
+    }
+
+    // Get completed remittance totals for a token and purpose
+    pub fn get_purpose_stats(env: Env, token: Address, purpose: PurposeCode) -> PurposeStats {
+        env.storage()
+            .instance()
+            .get::<_, PurposeStats>(&DataKey::PurposeStats(token, purpose))
+            .unwrap_or(PurposeStats { count: 0, volume: 0 })
+    }
+
+    // Get aggregate statistics for a token and corridor over a daily or monthly period
+    pub fn get_stats(env: Env, token: Address, corridor: String, period: StatsPeriod) -> VolumeStats {
+        read_volume_stats(&env, &DataKey::Stats(token, corridor, period))
+    }
+
+    // Get payout corridor details
+    pub fn get_corridor(env: Env, corridor: String) -> Corridor {
+        env.storage()
+            .instance()
+            .get::<_, Corridor>(&DataKey::Corridor(corridor))
+            .expect("Corridor not found")
+    }
+
+    // Get SLA statistics for an operator
+    pub fn get_operator_stats(env: Env, operator: Address) -> OperatorStats {
+        read_operator_stats(&env, &operator)
+    }
+
+    // Create or update a payout corridor's SLA windows (admin only)
+    pub fn set_corridor(
+        env: Env,
+        admin: Address,
+        corridor: String,
+        accept_window: u64,
+        payout_sla: u64,
+    ) {
+        // Verify admin
+        admin.require_auth();
+        require_admin(&env, &admin);
+
+        if accept_window == 0 || payout_sla == 0 {
+            panic!("SLA windows must be positive");
+        }
+
+        let mut config = env
+            .storage()
+            .instance()
+            .get::<_, Corridor>(&DataKey::Corridor(corridor.clone()))
+            .unwrap_or(Corridor {
+                operators: vec![&env],
+                next_operator: 0,
+                accept_window,
+                payout_sla,
+                allowed_purposes: vec![&env],
+            });
+        config.accept_window = accept_window;
+        config.payout_sla = payout_sla;
+
+        env.storage()
+            .instance()
+            .set(&DataKey::Corridor(corridor), &config);
+    }
+
+    // Restrict the purposes allowed in a corridor; an empty list allows every purpose (admin only)
+    pub fn set_corridor_purposes(
+        env: Env,
+        admin: Address,
+        corridor: String,
+        allowed_purposes: Vec<PurposeCode>,
+    ) {
+        // Verify admin
+        admin.require_auth();
+        require_admin(&env, &admin);
+
+        let mut config = env
+            .storage()
+            .instance()
+            .get::<_, Corridor>(&DataKey::Corridor(corridor.clone()))
+            .expect("Corridor not found");
+        config.allowed_purposes = allowed_purposes;
+
+        env.storage()
+            .instance()
+            .set(&DataKey::Corridor(corridor), &config);
+    }
+
+    // Register a payout operator for a corridor (admin only)
+    pub fn add_operator(env: Env, admin: Address, corridor: String, operator: Address) {
+        // Verify admin
+        admin.require_auth();
+        require_admin(&env, &admin);
+
+        let mut config = env
+            .storage()
+            .instance()
+            .get::<_, Corridor>(&DataKey::Corridor(corridor.clone()))
+            .expect("Corridor not found");
+
+        if config.operators.contains(&operator) {
+            panic!("Operator already registered");
+        }
+
+        config.operators.push_back(operator);
+        env.storage()
+            .instance()
+            .set(&DataKey::Corridor(corridor), &config);
+    }
+
+    // Remove a payout operator from a corridor (admin only)
+    // Remittances already assigned to the operator keep their assignment
+    pub fn remove_operator(env: Env, admin: Address, corridor: String, operator: Address) {
+        // Verify admin
+        admin.require_auth();
+        require_admin(&env, &admin);
+
+        let mut config = env
+            .storage()
+            .instance()
+            .get::<_, Corridor>(&DataKey::Corridor(corridor.clone()))
+            .expect("Corridor not found");
+
+        let index = config
+            .operators
+            .first_index_of(&operator)
+            .expect("Operator not registered for corridor");
+        config.operators.remove(index);
+
+        if config.next_operator >= config.operators.len() {
+            config.next_operator = 0;
+        }
+
+        env.storage()
+            .instance()
+            .set(&DataKey::Corridor(corridor), &config);
+    }
+
+    // Set the token operators post their collateral in (admin only)
+    pub fn set_bond_token(env: Env, admin: Address, token: Address) {
+        // Verify admin
+        admin.require_auth();
+        require_admin(&env, &admin);
+
+        if env.storage().instance().has(&DataKey::BondToken) {
+            panic!("Bond token already set");
+        }
+
+        env.storage().instance().set(&DataKey::BondToken, &token);
+    }
+
+    // Set the arbiter allowed to slash operator bonds (admin only)
+    pub fn set_arbiter(env: Env, admin: Address, arbiter: Address) {
+        // Verify admin
+        admin.require_auth();
+        require_admin(&env, &admin);
+
+        env.storage().instance().set(&DataKey::Arbiter, &arbiter);
+    }
+
+    // Post collateral to back an operator's in-flight payouts
+    pub fn post_bond(env: Env, operator: Address, amount: i128) -> OperatorBond {
+        // Verify operator
+        operator.require_auth();
+
+        // Verify amount is positive
+        if amount <= 0 {
+            panic!("Amount must be positive");
+        }
+
+        let bond_token = env
+            .storage()
+            .instance()
+            .get::<_, Address>(&DataKey::BondToken)
+            .expect("Bond token not set");
+
+        // Transfer collateral from operator to contract
+        let token_client = token::Client::new(&env, &bond_token);
+        token_client.transfer(&operator, &env.current_contract_address(), &amount);
+
+        let mut bond = get_bond(&env, &operator);
+        bond.amount += amount;
+        env.storage()
+            .instance()
+            .set(&DataKey::OperatorBond(operator), &bond);
+
+        bond
+    }
+
+    // Set how long (in seconds) a completed payout stays backed by the operator's bond (admin only)
+    pub fn set_challenge_period(env: Env, admin: Address, period: u64) {
+        // Verify admin
+        admin.require_auth();
+        require_admin(&env, &admin);
+
+        env.storage().instance().set(&DataKey::ChallengePeriod, &period);
+    }
+
+    // Withdraw collateral not backing in-flight payouts or payouts still in their challenge period
+    pub fn withdraw_bond(env: Env, operator: Address, amount: i128) -> OperatorBond {
+        // Verify operator
+        operator.require_auth();
+
+        // Verify amount is positive
+        if amount <= 0 {
+            panic!("Amount must be positive");
+        }
+
+        let mut bond = get_bond(&env, &operator);
+        if bond.amount - bond.exposure < amount {
+            panic!("Bond is backing in-flight or challengeable payouts");
+        }
+
+        let bond_token = env
+            .storage()
+            .instance()
+            .get::<_, Address>(&DataKey::BondToken)
+            .expect("Bond token not set");
+
+        bond.amount -= amount;
+        env.storage()
+            .instance()
+            .set(&DataKey::OperatorBond(operator.clone()), &bond);
+
+        // Return collateral to the operator
+        let token_client = token::Client::new(&env, &bond_token);
+        token_client.transfer(&env.current_contract_address(), &operator, &amount);
+
+        bond
+    }
+
+    // Slash an operator's bond to compensate the sender of a remittance
+    // that was marked completed but never paid out (admin or arbiter)
+    pub fn slash(
+        env: Env,
+        caller: Address,
+        operator: Address,
+        amount: i128,
+        remittance_id: BytesN<32>,
+    ) -> OperatorBond {
+        // Verify caller
+        caller.require_auth();
+
+        let stored_admin = env
+            .storage()
+            .instance()
+            .get::<_, Address>(&DataKey::Admin)
+            .expect("Contract not initialized");
+        let arbiter = env.storage().instance().get::<_, Address>(&DataKey::Arbiter);
+
+        if caller != stored_admin && Some(caller) != arbiter {
+            panic!("Only admin or arbiter can slash bonds");
+        }
+
+        // Verify amount is positive
+        if amount <= 0 {
+            panic!("Amount must be positive");
+        }
+
+        // Get remittance data
+        let mut remittance = env
+            .storage()
+            .instance()
+            .get::<_, Remittance>(&DataKey::Remittance(remittance_id.clone()))
+            .expect("Remittance not found");
+
+        if remittance.status != RemittanceStatus::Completed {
+            panic!("Remittance is not completed");
+        }
+
+        if remittance.operator != Some(operator.clone()) {
+            panic!("Remittance was not paid out by this operator");
+        }
+
+        // Compensation is capped at the remittance amount
+        if remittance.slashed + amount > remittance.amount {
+            panic!("Slash exceeds remittance amount");
+        }
+
+        let mut bond = get_bond(&env, &operator);
+        if bond.amount < amount {
+            panic!("Insufficient bond");
+        }
+
+        bond.amount -= amount;
+        bond.slashed += amount;
+        env.storage()
+            .instance()
+            .set(&DataKey::OperatorBond(operator), &bond);
+
+        remittance.slashed += amount;
+        apply_refund_address(&env, &mut remittance);
+        env.storage()
+            .instance()
+            .set(&DataKey::Remittance(remittance_id), &remittance);
+
+        // Compensate the sender at their refund address from the bond
+        let bond_token = env
+            .storage()
+            .instance()
+            .get::<_, Address>(&DataKey::BondToken)
+            .expect("Bond token not set");
+        let token_client = token::Client::new(&env, &bond_token);
+        token_client.transfer(&env.current_contract_address(), &remittance.refund_address, &amount);
+
+        bond
+    }
+
+    // Get an operator's bond
+    pub fn get_operator_bond(env: Env, operator: Address) -> OperatorBond {
+        get_bond(&env, &operator)
+    }
+
+    // Set the amount at or above which remittances in a token need a travel-rule commitment (admin only)
+    pub fn set_travel_rule_threshold(env: Env, admin: Address, token: Address, threshold: i128) {
+        // Verify admin
+        admin.require_auth();
+        require_admin(&env, &admin);
+
+        if threshold <= 0 {
+            panic!("Threshold must be positive");
+        }
+
+        env.storage()
+            .instance()
+            .set(&DataKey::TravelRuleThreshold(token), &threshold);
+    }
+
+    // Set the address fees are paid into (admin only)
+    pub fn set_treasury(env: Env, admin: Address, treasury: Address) {
+        // Verify admin
+        admin.require_auth();
+        require_admin(&env, &admin);
+
+        env.storage().instance().set(&DataKey::Treasury, &treasury);
+    }
+
+    // Set the cooling-off window (in seconds) during which senders cancel for free,
+    // and the cancellation fee charged after it (admin only)
+    pub fn set_cancellation_policy(env: Env, admin: Address, window: u64, fee_percentage: i128) {
+        // Verify admin
+        admin.require_auth();
+        require_admin(&env, &admin);
+
+        // Verify fee percentage is reasonable
+        if fee_percentage < 0 || fee_percentage > 1000 {
+            panic!("Fee percentage must be less than 10% (1000 basis points)");
+        }
+
+        env.storage().instance().set(&DataKey::CancellationWindow, &window);
+        env.storage()
+            .instance()
+            .set(&DataKey::CancellationFee, &fee_percentage);
+    }
+
+    // Update fee percentage (admin only)
+    pub fn update_fee_percentage(env: Env, admin: Address, fee_percentage: i128) {
+        // Verify admin
+        admin.require_auth();
+
+        // Check if caller is admin
+        let stored_admin = env
+            .storage()
+            .instance()
+            .get::<_, Address>(&DataKey::Admin)
+            .expect("Contract not initialized");
+
+        if stored_admin != admin {
+            panic!("Only admin can update fee percentage");
+        }
+
+        // Verify fee percentage is reasonable
+        if fee_percentage > 1000 {
+            panic!("Fee percentage must be less than 10% (1000 basis points)");
+        }
+
+        // Update fee percentage
+        env.storage().instance().set(&DataKey::FeePercentage, &fee_percentage);
+    }
+
+
+}
+
+// Check that the caller is the stored admin
+fn require_admin(env: &Env, admin: &Address) {
+    let stored_admin = env
+        .storage()
+        .instance()
+        .get::<_, Address>(&DataKey::Admin)
+        .expect("Contract not initialized");
+
+    if stored_admin != *admin {
+        panic!("Not authorized");
+    }
+}
+
+// Get the storage schema version; contracts deployed before versioning are version 1
+fn schema_version(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get::<_, u32>(&DataKey::SchemaVersion)
+        .unwrap_or(1)
+}
+
+// Get an operator's SLA statistics, defaulting to zero
+fn read_operator_stats(env: &Env, operator: &Address) -> OperatorStats {
+    env.storage()
+        .instance()
+        .get::<_, OperatorStats>(&DataKey::OperatorStats(operator.clone()))
+        .unwrap_or(OperatorStats {
+            assigned: 0,
+            accepted: 0,
+            completed: 0,
+            completed_on_time: 0,
+            breaches: 0,
+            total_payout_time: 0,
+        })
+}
+
+// Normalise a recipient identifier: drop spaces and separators, upper-case letters
+fn normalize_identifier(env: &Env, identifier: &String) -> Bytes {
+    try_normalize_identifier(env, identifier).expect("Invalid recipient identifier")
+}
+
+// Normalize a recipient identifier, or None if it is empty or too long
+fn try_normalize_identifier(env: &Env, identifier: &String) -> Option<Bytes> {
+    let len = identifier.len() as usize;
+    if len == 0 || len > MAX_IDENTIFIER_LEN {
+        return None;
+    }
+
+    let mut buf = [0u8; MAX_IDENTIFIER_LEN];
+    identifier.copy_into_slice(&mut buf[..len]);
+
+    let mut normalized = Bytes::new(env);
+    for c in buf[..len].iter() {
+        match c {
+            b' ' | b'-' | b'.' | b'(' | b')' => {}
+            b'a'..=b'z' => normalized.push_back(c.to_ascii_uppercase()),
+            _ => normalized.push_back(*c),
+        }
+    }
+    Some(normalized)
+}
+
+// Add a remittance to the recipient hash index
+fn index_recipient(env: &Env, recipient: &BytesN<32>, id: &BytesN<32>) {
+    let mut remittances = env
+        .storage()
+        .instance()
+        .get::<_, Vec<BytesN<32>>>(&DataKey::RecipientRemittances(recipient.clone()))
+        .unwrap_or(vec![env]);
+    remittances.push_back(id.clone());
+    env.storage()
+        .instance()
+        .set(&DataKey::RecipientRemittances(recipient.clone()), &remittances);
+}
+
+// Remove a remittance from the recipient hash index
+fn unindex_recipient(env: &Env, recipient: &BytesN<32>, id: &BytesN<32>) {
+    let mut remittances = env
+        .storage()
+        .instance()
+        .get::<_, Vec<BytesN<32>>>(&DataKey::RecipientRemittances(recipient.clone()))
+        .unwrap_or(vec![env]);
+    if let Some(index) = remittances.first_index_of(id) {
+        remittances.remove(index);
+    }
+    env.storage()
+        .instance()
+        .set(&DataKey::RecipientRemittances(recipient.clone()), &remittances);
+}
+
+// Deposit a remittance net of fees into a family pool
+// The pool pulls the tokens from this contract, so the transfer is pre-authorized
+fn deposit_to_pool(env: &Env, remittance: &Remittance, deposit: &PoolDeposit) {
+    let amount = remittance.amount - remittance.fee;
+
+    env.authorize_as_current_contract(vec![
+        env,
+        InvokerContractAuthEntry::Contract(SubContractInvocation {
+            context: ContractContext {
+                contract: remittance.token.clone(),
+                fn_name: Symbol::new(env, "transfer"),
+                args: (
+                    env.current_contract_address(),
+                    deposit.contract.clone(),
+                    amount,
+                )
+                    .into_val(env),
+            },
+            sub_invocations: vec![env],
+        }),
+    ]);
+
+    let pool_client = FamilyPoolClient::new(env, &deposit.contract);
+    pool_client.deposit_remittance(
+        &deposit.pool_id,
+        &env.current_contract_address(),
+        &remittance.sender,
+        &amount,
+        &remittance.id,
+    );
+}
+
+// Get the purchase details of a goods-purchase escrow
+fn get_purchase(remittance: &Remittance) -> Purchase {
+    match remittance.kind.clone() {
+        RemittanceKind::Purchase(purchase) => purchase,
+        _ => panic!("Remittance is not a purchase"),
+    }
+}
+
+// Complete a remittance by releasing the escrow to an on-chain payee and the fee to the treasury
+fn release_to(env: &Env, remittance: &mut Remittance, payee: &Address) {
+    remittance.status = RemittanceStatus::Completed;
+    remittance.completed_at = env.ledger().timestamp();
+
+    let token_client = token::Client::new(env, &remittance.token);
+    token_client.transfer(
+        &env.current_contract_address(),
+        payee,
+        &(remittance.amount - remittance.fee),
+    );
+    if remittance.fee > 0 {
+        token_client.transfer(&env.current_contract_address(), &treasury(env), &remittance.fee);
+    }
+
+    record_completion(env, remittance);
+
+    let net_amount = remittance.amount - remittance.fee;
+    issue_receipt(
+        env,
+        remittance,
+        net_amount,
+        10000,
+        Some(payee.clone()),
+        String::from_str(env, ""),
+    );
+}
+
+// Store the receipt of a completed remittance and record its digest on the remittance
+fn issue_receipt(
+    env: &Env,
+    remittance: &mut Remittance,
+    net_amount: i128,
+    rate: i128,
+    payee: Option<Address>,
+    payout_reference: String,
+) {
+    let receipt = Receipt {
+        remittance_id: remittance.id.clone(),
+        sender: remittance.sender.clone(),
+        recipient: remittance.recipient.clone(),
+        token: remittance.token.clone(),
+        amount: remittance.amount,
+        fee: remittance.fee,
+        net_amount,
+        rate,
+        corridor: remittance.corridor.clone(),
+        purpose: remittance.purpose,
+        operator: remittance.operator.clone(),
+        payee,
+        payout_reference,
+        created_at: remittance.created_at,
+        completed_at: remittance.completed_at,
+    };
+
+    remittance.receipt_digest = Some(env.crypto().sha256(&receipt.clone().to_xdr(env)));
+    env.storage()
+        .persistent()
+        .set(&DataKey::Receipt(remittance.id.clone()), &receipt);
+}
+
+// Apply a pending refund address change whose delay has passed
+fn apply_refund_address(env: &Env, remittance: &mut Remittance) {
+    if let Some(pending) = remittance.pending_refund_address.clone() {
+        if env.ledger().timestamp() >= remittance.refund_address_effective_at {
+            remittance.refund_address = pending;
+            remittance.pending_refund_address = None;
+        }
+    }
+}
+
+// Return the escrowed amount of a remittance to its refund address, less any
+// cancellation fee which is retained into the treasury
+// A pending refund address change applies once its delay has passed
+fn refund(env: &Env, remittance: &mut Remittance) {
+    apply_refund_address(env, remittance);
+
+    let token_client = token::Client::new(env, &remittance.token);
+    token_client.transfer(
+        &env.current_contract_address(),
+        &remittance.refund_address,
+        &(remittance.amount - remittance.cancellation_fee),
+    );
+    if remittance.cancellation_fee > 0 {
+        token_client.transfer(
+            &env.current_contract_address(),
+            &treasury(env),
+            &remittance.cancellation_fee,
+        );
+    }
+
+    record_stats(env, remittance, |stats| {
+        stats.refund_count += 1;
+        stats.refunds += remittance.amount - remittance.cancellation_fee;
+        stats.fees += remittance.cancellation_fee;
+    });
+}
+
+// Cancel a remittance and refund the sender, freeing the assigned operator's bond capacity
+fn finish_cancellation(env: &Env, remittance: &mut Remittance) {
+    remittance.status = RemittanceStatus::Cancelled;
+    remittance.completed_at = env.ledger().timestamp();
+
+    if let Some(operator) = remittance.operator.clone() {
+        release_exposure(env, &operator, remittance.amount);
+    }
+
+    refund(env, remittance);
+}
+
+// Get the address fees are paid into, defaulting to the admin
+fn treasury(env: &Env) -> Address {
+    env.storage()
+        .instance()
+        .get::<_, Address>(&DataKey::Treasury)
+        .unwrap_or_else(|| {
+            env.storage()
+                .instance()
+                .get::<_, Address>(&DataKey::Admin)
+                .expect("Contract not initialized")
+        })
+}
+
+// Add a completed remittance to the purpose and volume reporting aggregates
+fn record_completion(env: &Env, remittance: &Remittance) {
+    record_stats(env, remittance, |stats| stats.fees += remittance.fee);
+
+    let key = DataKey::PurposeStats(remittance.token.clone(), remittance.purpose);
+    let mut stats = env
+        .storage()
+        .instance()
+        .get::<_, PurposeStats>(&key)
+        .unwrap_or(PurposeStats { count: 0, volume: 0 });
+    stats.count += 1;
+    stats.volume += remittance.amount;
+    env.storage().instance().set(&key, &stats);
+}
+
+// Apply an update to the current daily and monthly statistics buckets of a remittance's
+// token and corridor
+// Buckets accumulate indefinitely, so they live in persistent rather than instance storage
+fn record_stats(env: &Env, remittance: &Remittance, update: impl Fn(&mut VolumeStats)) {
+    let (year, month, day) = civil_date(env.ledger().timestamp());
+    let periods = [
+        StatsPeriod::Daily(year * 10000 + month * 100 + day),
+        StatsPeriod::Monthly(year * 100 + month),
+    ];
+
+    for period in periods {
+        let key = DataKey::Stats(remittance.token.clone(), remittance.corridor.clone(), period);
+        let mut stats = read_volume_stats(env, &key);
+        update(&mut stats);
+        env.storage().persistent().set(&key, &stats);
+    }
+}
+
+// Get a statistics bucket, defaulting to zero
+fn read_volume_stats(env: &Env, key: &DataKey) -> VolumeStats {
+    env.storage()
+        .persistent()
+        .get::<_, VolumeStats>(key)
+        .unwrap_or(VolumeStats {
+            count: 0,
+            volume: 0,
+            fees: 0,
+            refund_count: 0,
+            refunds: 0,
+        })
+}
+
+// Convert a unix timestamp to a UTC (year, month, day)
+fn civil_date(timestamp: u64) -> (u32, u32, u32) {
+    let days = timestamp / 86400 + 719468;
+    let era = days / 146097;
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year as u32, month as u32, day as u32)
+}
+
+// Number of schema version 1 remittances, recorded on the first call that needs it
+// while the storage is not yet migrated
+fn legacy_count(env: &Env) -> u32 {
+    if let Some(count) = env.storage().instance().get::<_, u32>(&DataKey::LegacyCount) {
+        return count;
+    }
+
+    let count = env
+        .storage()
+        .instance()
+        .get::<_, u32>(&DataKey::RemittanceCount)
+        .unwrap_or(0);
+    env.storage().instance().set(&DataKey::LegacyCount, &count);
+    count
+}
+
+// Get an operator's bond, defaulting to zero
+// Locks whose challenge period has ended are released from the exposure
+fn get_bond(env: &Env, operator: &Address) -> OperatorBond {
+    let mut bond = env
+        .storage()
+        .instance()
+        .get::<_, OperatorBond>(&DataKey::OperatorBond(operator.clone()))
+        .unwrap_or(OperatorBond {
+            amount: 0,
+            exposure: 0,
+            slashed: 0,
+            locks: Vec::new(env),
+        });
+
+    let now = env.ledger().timestamp();
+    let mut locks = Vec::new(env);
+    for lock in bond.locks.iter() {
+        if lock.unlocks_at <= now {
+            bond.exposure -= lock.amount;
+        } else {
+            locks.push_back(lock);
+        }
+    }
+    bond.locks = locks;
+
+    bond
+}
+
+// Keep a completed remittance's exposure on the operator's bond until its challenge period ends
+fn lock_exposure(env: &Env, operator: &Address, remittance: &Remittance) {
+    let period = env
+        .storage()
+        .instance()
+        .get::<_, u64>(&DataKey::ChallengePeriod)
+        .unwrap_or(DEFAULT_CHALLENGE_PERIOD);
+
+    let mut bond = get_bond(env, operator);
+    bond.locks.push_back(BondLock {
+        remittance_id: remittance.id.clone(),
+        amount: remittance.amount,
+        unlocks_at: remittance.completed_at + period,
+    });
+    env.storage()
+        .instance()
+        .set(&DataKey::OperatorBond(operator.clone()), &bond);
+}
+
+// Whether remittances in `token` can be backed by operator bonds
+fn is_bond_token(env: &Env, token: &Address) -> bool {
+    env.storage().instance().get::<_, Address>(&DataKey::BondToken) == Some(token.clone())
+}
+
+// Release an operator's exposure once a remittance is settled or moved away
+fn release_exposure(env: &Env, operator: &Address, amount: i128) {
+    let mut bond = get_bond(env, operator);
+    bond.exposure -= amount;
+    env.storage()
+        .instance()
+        .set(&DataKey::OperatorBond(operator.clone()), &bond);
+}
+
+// Pick the next operator of a corridor in round-robin order, skipping `exclude`
+// and operators whose bond cannot cover the remittance
+fn next_operator(
+    env: &Env,
+    corridor: &mut Corridor,
+    exclude: Option<&Address>,
+    remittance: &Remittance,
+) -> Option<Address> {
+    if !is_bond_token(env, &remittance.token) {
+        return None;
+    }
+
+    let amount = remittance.amount;
+    let count = corridor.operators.len();
+    for _ in 0..count {
+        let index = corridor.next_operator % count;
+        corridor.next_operator = (index + 1) % count;
+
+        let operator = corridor.operators.get(index).unwrap();
+        let bond = get_bond(env, &operator);
+        if Some(&operator) != exclude && bond.amount - bond.exposure >= amount {
+            return Some(operator);
+        }
+    }
+    None
+}
+
+// Assign a remittance to an operator and restart its acceptance window
+// The new operator has to acknowledge any travel-rule payload again
+// The operator's bond must cover the remittance on top of its current exposure
+fn assign(env: &Env, remittance: &mut Remittance, operator: Address) {
+    if let Some(previous) = remittance.operator.clone() {
+        release_exposure(env, &previous, remittance.amount);
+    }
+
+    // Exposure is compared with the bond, so both must be in the bond token
+    if !is_bond_token(env, &remittance.token) {
+        panic!("Remittance token is not the bond token");
+    }
+
+    let mut bond = get_bond(env, &operator);
+    if bond.amount - bond.exposure < remittance.amount {
+        panic!("Operator bond does not cover remittance");
     }
+    bond.exposure += remittance.amount;
+    env.storage()
+        .instance()
+        .set(&DataKey::OperatorBond(operator.clone()), &bond);
 
+    let mut stats = read_operator_stats(env, &operator);
+    stats.assigned += 1;
+    env.storage()
+        .instance()
+        .set(&DataKey::OperatorStats(operator.clone()), &stats);
 
+    remittance.operator = Some(operator);
+    remittance.assigned_at = env.ledger().timestamp();
+    remittance.accepted_at = 0;
+    remittance.travel_rule_acked_at = 0;
 }
@@ -5,13 +5,16 @@ set -e
 
 # Check if network parameter is provided
 if [ -z "$1" ]; then
-  echo "Usage: $0 <network> [sender_key_name]"
-  echo "Example: $0 testnet sender"
+  echo "Usage: $0 <network> [sender_key_name] [admin_key_name]"
+  echo "Example: $0 testnet sender deployer"
   exit 1
 fi
 
 NETWORK=$1
 SENDER_KEY=${2:-deployer}
+ADMIN_KEY=${3:-deployer}
+TOKEN_ADDRESS=${TOKEN_ADDRESS:?TOKEN_ADDRESS must be set}
+CORRIDOR=${CORRIDOR:-USDC-TZS}
 
 # Load contract IDs from .env.contracts file
 ENV_CONTRACTS_FILE="$(dirname "$0")/../../.env.contracts"
@@ -38,8 +41,49 @@ else
   NETWORK_PASSPHRASE="Test SDF Future Network ; October 2022"
 fi
 
-# Get sender public key
+# Get sender and admin public keys
 SENDER_ADDRESS=$(soroban keys address "$SENDER_KEY")
+ADMIN_ADDRESS=$(soroban keys address "$ADMIN_KEY")
+
+# Configure the corridor (1 hour to accept, 24 hours to pay out)
+echo "Configuring corridor $CORRIDOR..."
+soroban contract invoke \
+  --id "$REMITTANCE_CONTRACT_ID" \
+  --source-account "$ADMIN_KEY" \
+  --network "$NETWORK" \
+  --network-passphrase "$NETWORK_PASSPHRASE" \
+  -- \
+  set_corridor \
+  --admin "$ADMIN_ADDRESS" \
+  --corridor "$CORRIDOR" \
+  --accept_window 3600 \
+  --payout_sla 86400
+
+# Operators post their bonds in the bond token, which can only be set once
+if [ -n "$BOND_TOKEN_ADDRESS" ]; then
+  echo "Setting bond token..."
+  soroban contract invoke \
+    --id "$REMITTANCE_CONTRACT_ID" \
+    --source-account "$ADMIN_KEY" \
+    --network "$NETWORK" \
+    --network-passphrase "$NETWORK_PASSPHRASE" \
+    -- \
+    set_bond_token \
+    --admin "$ADMIN_ADDRESS" \
+    --token "$BOND_TOKEN_ADDRESS" || echo "Bond token already set"
+fi
+
+# Hash the recipient phone number with a random salt; the salt is shared with the operator off-chain
+RECIPIENT_SALT=$(openssl rand -hex 32)
+RECIPIENT_HASH=$(soroban contract invoke \
+  --id "$REMITTANCE_CONTRACT_ID" \
+  --source-account "$SENDER_KEY" \
+  --network "$NETWORK" \
+  --network-passphrase "$NETWORK_PASSPHRASE" \
+  -- \
+  hash_recipient \
+  --identifier "+255 712 345 678" \
+  --salt "$RECIPIENT_SALT" | tr -d '"')
 
 # Create a test remittance
 echo "Creating test remittance..."
@@ -51,8 +95,13 @@ REMITTANCE_ID=$(soroban contract invoke \
   -- \
   create_remittance \
   --sender "$SENDER_ADDRESS" \
-  --recipient "{\"string\":\"255712345678\"}" \
-  --amount 1000000)
+  --recipient "$RECIPIENT_HASH" \
+  --token "$TOKEN_ADDRESS" \
+  --amount 1000000 \
+  --corridor "$CORRIDOR" \
+  --purpose FamilySupport \
+  --memo "School fees" \
+  --kind Payout)
 
 echo "Test remittance created with ID: $REMITTANCE_ID"
 echo "Stellar Expert URL: https://stellar.expert/explorer/$NETWORK/contract/$REMITTANCE_CONTRACT_ID"
@@ -4,6 +4,10 @@ use soroban_sdk::{
 };
 use soroban_auth::{Identifier, Signature};
 
+// Storage schema version written by this code
+// Version 1 is the original layout, before the schema version was stored
+const CURRENT_SCHEMA_VERSION: u32 = 2;
+
 // Token data structure
 #[derive(Clone)]
 #[contracttype]
@@ -30,6 +34,7 @@ pub enum DataKey {
     TokenByCountry(String), // By country code
     TokenList,
     ExchangeRateOracle,
+    SchemaVersion,
 }
 
 #[contract]
@@ -48,6 +53,71 @@ impl TokenWrapperContract {
         env.storage().instance().set(&DataKey::Admin, &admin);
         env.storage().instance().set(&DataKey::ExchangeRateOracle, &exchange_rate_oracle);
         env.storage().instance().set(&DataKey::TokenList, &Vec::<String>::new(&env));
+        env.storage()
+            .instance()
+            .set(&DataKey::SchemaVersion, &CURRENT_SCHEMA_VERSION);
+    }
+
+    // Upgrade the contract code (admin only)
+    // Run migrate afterwards if the new code bumps the schema version
+    pub fn upgrade(env: Env, admin: Address, new_wasm_hash: BytesN<32>) {
+        // Verify admin
+        admin.require_auth();
+
+        // Check if caller is admin
+        let stored_admin = env
+            .storage()
+            .instance()
+            .get::<_, Address>(&DataKey::Admin)
+            .expect("Contract not initialized");
+
+        if admin != stored_admin {
+            panic!("Not authorized");
+        }
+
+        env.deployer().update_current_contract_wasm(new_wasm_hash);
+    }
+
+    // Migrate storage to the current schema version (admin only)
+    // Token records are unchanged between versions 1 and 2, so only the version is recorded.
+    // Returns the number of converted records.
+    pub fn migrate(env: Env, admin: Address) -> u32 {
+        // Verify admin
+        admin.require_auth();
+
+        // Check if caller is admin
+        let stored_admin = env
+            .storage()
+            .instance()
+            .get::<_, Address>(&DataKey::Admin)
+            .expect("Contract not initialized");
+
+        if admin != stored_admin {
+            panic!("Not authorized");
+        }
+
+        let version = env
+            .storage()
+            .instance()
+            .get::<_, u32>(&DataKey::SchemaVersion)
+            .unwrap_or(1);
+        if version >= CURRENT_SCHEMA_VERSION {
+            panic!("Storage already migrated");
+        }
+
+        env.storage()
+            .instance()
+            .set(&DataKey::SchemaVersion, &CURRENT_SCHEMA_VERSION);
+
+        0
+    }
+
+    // Get the storage schema version
+    pub fn get_schema_version(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get::<_, u32>(&DataKey::SchemaVersion)
+            .unwrap_or(1)
     }
 
     // Register a new token